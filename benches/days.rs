@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use aoc2020::*;
+
+fn input(day: u8) -> String {
+    let mut input = String::new();
+    aoc2020::input_file(day)
+        .unwrap()
+        .read_to_string(&mut input)
+        .unwrap();
+
+    input
+}
+
+/// Parse and both parts of a day, against its real input (and loading the
+/// parsed input when the cache is enabled with `AOC_CACHE_DIR`)
+fn bench_solution<S>(c: &mut Criterion)
+where
+    S: Solution,
+    S::Input: Serialize + DeserializeOwned,
+{
+    let input = input(S::DAY);
+    let cache = Cache::from_env();
+    let parsed = match &cache {
+        Some(cache) => cache.parse::<S>(input.as_bytes()).unwrap(),
+        None => S::parse(input.as_bytes()).unwrap(),
+    };
+
+    let mut group = c.benchmark_group(format!("day{:0>2}", S::DAY));
+    group.bench_function("parse", |b| b.iter(|| S::parse(input.as_bytes())));
+    if let Some(cache) = &cache {
+        group.bench_function("load", |b| b.iter(|| cache.parse::<S>(input.as_bytes())));
+    }
+    group.bench_function("part 1", |b| {
+        b.iter_batched(|| parsed.clone(), S::part_1, BatchSize::SmallInput)
+    });
+    group.bench_function("part 2", |b| {
+        b.iter_batched(|| parsed.clone(), S::part_2, BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+fn bench_day01(c: &mut Criterion) {
+    let expenses = day01::parse(Cursor::new(input(1))).unwrap();
+
+    c.bench_function("day01 part 1 combinations", |b| {
+        b.iter(|| day01::solve_combinations(&expenses, 2, 2020))
+    });
+    c.bench_function("day01 part 2 combinations", |b| {
+        b.iter(|| day01::solve_combinations(&expenses, 3, 2020))
+    });
+}
+
+fn bench_day05(c: &mut Criterion) {
+    let seats = day05::parse(Cursor::new(input(5))).unwrap();
+
+    c.bench_function("day05 part 2 naive", |b| {
+        b.iter(|| day05::part_2_naive(seats.clone()))
+    });
+    c.bench_function("day05 part 2 one pass", |b| {
+        b.iter(|| day05::part_2_one_pass(&seats))
+    });
+}
+
+fn bench_day15(c: &mut Criterion) {
+    use day15::{simulate_to, SizedMap, VecMap};
+
+    c.bench_function("day15 hash map", |b| {
+        b.iter(|| simulate_to::<HashMap<usize, usize>>(10_000, &[0, 3, 6]))
+    });
+    c.bench_function("day15 vec map", |b| {
+        b.iter(|| simulate_to::<VecMap<usize>>(10_000, &[0, 3, 6]))
+    });
+    c.bench_function("day15 sized map", |b| {
+        b.iter(|| simulate_to::<SizedMap<usize, 10_000>>(10_000, &[0, 3, 6]))
+    });
+}
+
+criterion_group!(
+    days,
+    bench_solution::<day01::Day01>,
+    bench_solution::<day02::Day02>,
+    bench_solution::<day03::Day03>,
+    bench_solution::<day04::Day04>,
+    bench_solution::<day05::Day05>,
+    bench_solution::<day06::Day06>,
+    bench_solution::<day07::Day07>,
+    bench_solution::<day08::Day08>,
+    bench_solution::<day09::Day09>,
+    bench_solution::<day10::Day10>,
+    bench_solution::<day11::Day11>,
+    bench_solution::<day12::Day12>,
+    bench_solution::<day13::Day13>,
+    bench_solution::<day14::Day14>,
+    bench_solution::<day16::Day16>,
+    bench_solution::<day17::Day17>,
+    bench_solution::<day18::Day18>,
+    bench_solution::<day19::Day19>,
+    bench_solution::<day20::Day20>,
+    bench_solution::<day21::Day21>,
+    bench_solution::<day22::Day22>,
+    bench_solution::<day24::Day24>,
+    bench_solution::<day25::Day25>,
+);
+// A single iteration of their part 2 takes seconds
+criterion_group! {
+    name = slow_days;
+    config = Criterion::default().sample_size(10);
+    targets = bench_solution::<day15::Day15>, bench_solution::<day23::Day23>
+}
+criterion_group!(alternatives, bench_day01, bench_day05, bench_day15);
+criterion_main!(days, slow_days, alternatives);
@@ -0,0 +1,9 @@
+#![no_main]
+
+use aoc2020::{day20::Day20, Solution};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Errors are fine, only panics are bugs
+    let _ = Day20::parse(data);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use aoc2020::{day21::Day21, Solution};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Errors are fine, only panics are bugs
+    let _ = Day21::parse(data);
+});
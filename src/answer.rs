@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::io;
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// Answer to a part of a puzzle
+#[derive(Debug, Clone)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Answer::Number(a), Answer::Number(b)) => a == b,
+            (Answer::Text(a), Answer::Text(b)) => a == b,
+            // Some text answers are only made of digits (day 23 labels)
+            _ => self.to_string() == other.to_string(),
+        }
+    }
+}
+
+impl Eq for Answer {}
+
+macro_rules! answer_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Number(n as i128)
+                }
+            }
+        )*
+    };
+}
+
+answer_from_number!(i32, i64, isize, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+impl FromStr for Answer {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(n) => Answer::Number(n),
+            Err(_) => Answer::Text(s.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+/// Expected answers, indexed by day and part
+pub type Answers = HashMap<(u8, u8), Answer>;
+
+/// Parse a list of answers, with one `<day> <part> <answer>` per line.
+/// Empty lines and lines starting with `#` are ignored.
+pub fn read_answers(input: impl BufRead) -> io::Result<Answers> {
+    let mut answers = HashMap::new();
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || Error::new(ErrorKind::InvalidData, format!("Invalid answer: {}", line));
+
+        let mut words = line.splitn(3, ' ');
+        let day = words
+            .next()
+            .and_then(|d| d.parse().ok())
+            .ok_or_else(invalid)?;
+        let part = words
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let answer = words.next().ok_or_else(invalid)?;
+
+        answers.insert((day, part), answer.parse().unwrap());
+    }
+
+    Ok(answers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            Answer::from(20899048083289_usize).to_string(),
+            "20899048083289"
+        );
+        assert_eq!(Answer::from(-42_isize).to_string(), "-42");
+        assert_eq!(
+            Answer::from("mxmxvkd,sqjhc,fvjkl").to_string(),
+            "mxmxvkd,sqjhc,fvjkl"
+        );
+    }
+
+    #[test]
+    fn numbers_of_any_type_are_equal() {
+        assert_eq!(Answer::from(295_u64), Answer::from(295_i32));
+    }
+
+    #[test]
+    fn digits_are_equal_to_numbers() {
+        assert_eq!(Answer::from("67384529"), "67384529".parse().unwrap());
+        assert_ne!(Answer::from("0123"), Answer::from(123));
+    }
+
+    #[test]
+    fn parse_answers() {
+        let input = "# day part answer
+1 1 514579
+
+21 2 mxmxvkd,sqjhc,fvjkl
+";
+        let answers = read_answers(io::Cursor::new(input)).unwrap();
+
+        assert_eq!(answers.len(), 2);
+        assert_eq!(answers[&(1, 1)], Answer::from(514579));
+        assert_eq!(answers[&(21, 2)], Answer::from("mxmxvkd,sqjhc,fvjkl"));
+    }
+
+    #[test]
+    fn parse_invalid_answers() {
+        assert!(read_answers(io::Cursor::new("1 1")).is_err());
+        assert!(read_answers(io::Cursor::new("one 1 2")).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! On-disk cache of parsed inputs, so that repeated runs skip parsing.
+//!
+//! Each parsed input is saved in a compact binary form (with
+//! [postcard](https://docs.rs/postcard)), in a file named after its day and a
+//! hash of the input it was parsed from and of the version of the crate, so
+//! that the parsers of another version don't reuse the files. A file which
+//! can't be read back is parsed and saved again.
+
+use std::any::type_name;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Result, Solution};
+
+/// Environment variable enabling the cache, in this directory
+pub const CACHE_DIR_VAR: &str = "AOC_CACHE_DIR";
+
+/// Directory of parsed inputs
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Cache in `$AOC_CACHE_DIR`, `None` when unset
+    pub fn from_env() -> Option<Self> {
+        env::var_os(CACHE_DIR_VAR).map(Self::new)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File of the parsed input of a day, from the hash of its input
+    pub fn path(&self, day: u8, hash: u64) -> PathBuf {
+        self.dir.join(format!("day_{:0>2}-{:016x}.bin", day, hash))
+    }
+
+    /// Parsed input of a day, loaded from the cache, or parsed and saved in it
+    /// when it is missing
+    pub fn parse<S>(&self, mut input: impl BufRead) -> Result<S::Input>
+    where
+        S: Solution,
+        S::Input: Serialize + DeserializeOwned,
+    {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+
+        // Another version or type of parsed input gets another file
+        let key = [
+            env!("CARGO_PKG_VERSION").as_bytes(),
+            type_name::<S::Input>().as_bytes(),
+            &bytes,
+        ];
+        let path = self.path(S::DAY, hash(&key));
+
+        if let Ok(saved) = fs::read(&path) {
+            if let Ok(parsed) = postcard::from_bytes(&saved) {
+                return Ok(parsed);
+            }
+        }
+
+        let parsed = S::parse(&bytes[..])?;
+
+        let saved = postcard::to_stdvec(&parsed).map_err(io::Error::other)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, saved)?;
+
+        Ok(parsed)
+    }
+}
+
+/// FNV-1a hash of all the parts, each preceded by its length so that
+/// splitting the same bytes in other parts gives another hash. Unlike the
+/// hasher of the standard library, it is the same with every version of Rust.
+fn hash(parts: &[&[u8]]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+
+    for part in parts {
+        let len = (part.len() as u64).to_le_bytes();
+
+        for &byte in len.iter().chain(part.iter()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day20::{self, Day20};
+    use crate::Day;
+
+    #[test]
+    fn parsed_inputs_are_saved() {
+        let cache =
+            Cache::new(env::temp_dir().join(format!("aoc2020-cache-{}", std::process::id())));
+        let input = Day20.generate(1, Some(9));
+
+        let parsed = cache.parse::<Day20>(input.as_bytes()).unwrap();
+        let path = fs::read_dir(cache.dir())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+
+        let loaded = cache.parse::<Day20>(input.as_bytes()).unwrap();
+        assert_eq!(
+            Day20::part_1(loaded).unwrap(),
+            Day20::part_1(parsed).unwrap()
+        );
+
+        // What is in the file is loaded, without parsing the input again
+        let other = Day20.generate(1, Some(16));
+        fs::write(
+            &path,
+            postcard::to_stdvec(&day20::parse(other.as_bytes()).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(cache.parse::<Day20>(input.as_bytes()).unwrap().len(), 16);
+
+        // Files which can't be read are replaced
+        fs::write(&path, b"corrupted").unwrap();
+        assert_eq!(cache.parse::<Day20>(input.as_bytes()).unwrap().len(), 9);
+        assert_ne!(fs::read(&path).unwrap(), b"corrupted");
+
+        assert!(cache.parse::<Day20>("Tile 1:\n".as_bytes()).is_err());
+
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn every_day_loads_its_input() {
+        let cache =
+            Cache::new(env::temp_dir().join(format!("aoc2020-days-{}", std::process::id())));
+
+        for day in crate::DAYS {
+            let input = day.generate(0, Some(10));
+            let parsed = day.parse(&mut input.as_bytes()).unwrap();
+
+            for _ in 0..2 {
+                let cached = day.parse_cached(&mut input.as_bytes(), &cache).unwrap();
+                assert_eq!(
+                    day.solve(&*cached, 1).unwrap().unwrap(),
+                    day.solve(&*parsed, 1).unwrap().unwrap(),
+                    "Day {}",
+                    day.day()
+                );
+            }
+        }
+
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn stable_hash() {
+        assert_eq!(hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(&[b"a"]), 0x529a_4ddc_8ff5_6bbf);
+        assert_ne!(hash(&[b"ab", b"c"]), hash(&[b"a", b"bc"]));
+        assert_ne!(hash(&[b"", b"a"]), hash(&[b"a", b""]));
+        assert_ne!(hash(&[b"a"]), hash(&[b"a", b""]));
+    }
+}
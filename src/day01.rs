@@ -1,36 +1,49 @@
-use std::io;
+//! Day 1: Report Repair
+
 use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let expenses = parse(input)?;
+use crate::error::Context;
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
 
-    let solution = match part {
-        1 => part_1(&expenses),
-        2 => part_2(&expenses),
-        _ => unimplemented!(),
-    };
+pub struct Day01;
+
+impl Solution for Day01 {
+    const DAY: u8 = 1;
+
+    type Input = Vec<i32>;
+    type Part1 = i32;
+    type Part2 = i32;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    println!("{}", solution.expect("No solution"));
+    fn part_1(expenses: Self::Input) -> Result<Self::Part1> {
+        part_1(&expenses).ok_or(Error::NoSolution)
+    }
 
-    Ok(())
+    fn part_2(expenses: Self::Input) -> Result<Self::Part2> {
+        part_2(&expenses).ok_or(Error::NoSolution)
+    }
 }
 
-fn parse(input: impl BufRead) -> io::Result<Vec<i32>> {
-    input
-        .lines()
-        .map(|l| {
-            l.and_then(|s| {
-                s.parse()
-                    .map_err(|e| Error::new(ErrorKind::InvalidInput, e))
-            })
-        })
-        .collect()
+impl Generate for Day01 {
+    const INPUT_SIZE: usize = 200;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+pub fn parse(input: impl BufRead) -> Result<Vec<i32>> {
+    records(input, |l| l.parse().at_column(1)).collect()
 }
 
-fn part_1(expenses: &[i32]) -> Option<i32> {
+pub fn part_1(expenses: &[i32]) -> Option<i32> {
     let mut expenses_set = BTreeSet::new();
 
     for expense in expenses {
@@ -47,7 +60,7 @@ fn part_1(expenses: &[i32]) -> Option<i32> {
     None
 }
 
-fn part_2(expenses: &[i32]) -> Option<i32> {
+pub fn part_2(expenses: &[i32]) -> Option<i32> {
     let mut expenses_set = BTreeSet::new();
 
     for (i, expense) in expenses.iter().enumerate() {
@@ -66,9 +79,106 @@ fn part_2(expenses: &[i32]) -> Option<i32> {
     None
 }
 
+/// Largest `k` which `k_sum` handles in reasonable memory on a real input
+pub const MAX_K: usize = 6;
+
+/// Indices (in increasing order) of all the `k` expenses summing up to `goal`,
+/// in lexicographic order.
+///
+/// Meet in the middle: the tuples of the last `k - k / 2` indices are stored by
+/// sum, and looked up for each tuple of the first `k / 2` indices, which takes
+/// about `n^(k - k / 2)` time and memory (`n` for pairs) instead of `n^k`.
+///
+/// That is `C(n, k - k / 2)` stored tuples, which for the 200 expenses of a
+/// real input is about 1.3 million with `k` up to 6 ([`MAX_K`]), but 65
+/// million with `k` of 7 or 8, and then billions.
+pub fn k_sum(expenses: &[i32], k: usize, goal: i64) -> Vec<Vec<usize>> {
+    use itertools::Itertools;
+
+    let sum = |indices: &[usize]| indices.iter().map(|&i| expenses[i] as i64).sum::<i64>();
+
+    // In lexicographic order, so each list is sorted by first index
+    let mut right = HashMap::<i64, Vec<Vec<usize>>>::new();
+    for indices in (0..expenses.len()).combinations(k - k / 2) {
+        right.entry(sum(&indices)).or_default().push(indices);
+    }
+
+    let mut tuples = Vec::new();
+
+    for left in (0..expenses.len()).combinations(k / 2) {
+        if let Some(rights) = right.get(&(goal - sum(&left))) {
+            // Only the tuples starting after the left one, so that each tuple
+            // of indices is found once
+            let start = left
+                .last()
+                .map_or(0, |&last| rights.partition_point(|r| r[0] <= last));
+
+            for right in &rights[start..] {
+                tuples.push([&left[..], right].concat());
+            }
+        }
+    }
+
+    tuples.sort_unstable();
+    tuples
+}
+
+/// Every distinct set of `k` expenses summing up to `goal`, as sorted values,
+/// with the number of tuples of indices (as found by `k_sum`) giving it.
+///
+/// Values repeated in the report can be used as many times as they appear in
+/// it, e.g. `1010 + 1010` with two entries of 1010, and negative amounts are
+/// summed like the others.
+pub fn distinct_k_sums(expenses: &[i32], k: usize, goal: i64) -> Vec<(Vec<i32>, u64)> {
+    let mut counts = BTreeMap::<i32, usize>::new();
+    for &expense in expenses {
+        *counts.entry(expense).or_default() += 1;
+    }
+
+    // No sum uses more than `k` copies of a value, so the others would only
+    // give more tuples of indices with the same values
+    let reduced: Vec<i32> = counts
+        .iter()
+        .flat_map(|(&expense, &count)| std::iter::repeat_n(expense, count.min(k)))
+        .collect();
+
+    // The reduced expenses are sorted, so the values of each sum are too
+    let mut sums: Vec<Vec<i32>> = k_sum(&reduced, k, goal)
+        .into_iter()
+        .map(|indices| indices.iter().map(|&i| reduced[i]).collect())
+        .collect();
+    sums.sort_unstable();
+    sums.dedup();
+
+    sums.into_iter()
+        .map(|values| {
+            let mut used = BTreeMap::<i32, usize>::new();
+            for &value in &values {
+                *used.entry(value).or_default() += 1;
+            }
+
+            let ways = used
+                .iter()
+                .map(|(value, &used)| binomial(counts[value], used))
+                .fold(1, u64::saturating_mul);
+
+            (values, ways)
+        })
+        .collect()
+}
+
+/// Number of ways to choose `r` items out of `n`, up to `u64::MAX`
+fn binomial(n: usize, r: usize) -> u64 {
+    (0..r as u64).fold(1, |ways, i| {
+        ways.checked_mul(n as u64 - i)
+            .map_or(u64::MAX, |ways| ways / (i + 1))
+    })
+}
+
 // With IterTools.combinations
 
-fn _solve_combinations(input: &[i32], k_combinations: usize, goal: i32) -> Option<i32> {
+/// Product of the first `k_combinations` expenses summing up to `goal`
+pub fn solve_combinations(input: &[i32], k_combinations: usize, goal: i32) -> Option<i32> {
     use itertools::Itertools;
 
     input
@@ -79,64 +189,192 @@ fn _solve_combinations(input: &[i32], k_combinations: usize, goal: i32) -> Optio
         .map(|v| v.iter().product::<i32>())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use test::Bencher;
+/// `size` expenses, with a single pair and a single triple summing up to 2020
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    use itertools::Itertools;
 
-    use std::fs::File;
-    use std::io::BufReader;
+    let sums = |expenses: &[i32], k| {
+        expenses
+            .iter()
+            .combinations(k)
+            .filter(|v| v.iter().copied().sum::<i32>() == 2020)
+            .count()
+    };
 
-    fn input() -> BufReader<File> {
-        aoc2020::input_file(1).unwrap()
-    }
+    let mut expenses = loop {
+        let pair = rng.range(1..1010) as i32;
+        let (x, y) = (rng.range(1..673) as i32, rng.range(1..673) as i32);
+        let planted = vec![pair, 2020 - pair, x, y, 2020 - x - y];
 
-    #[bench]
-    fn bench_part_1(b: &mut Bencher) {
-        let expenses = parse(input()).unwrap();
+        if planted.iter().unique().count() == 5 && sums(&planted, 2) == 1 && sums(&planted, 3) == 1
+        {
+            break planted;
+        }
+    };
 
-        b.iter(|| part_1(&expenses));
+    // The other expenses are over 1010, so only the small planted ones can
+    // add up to 2020 with them
+    let small: Vec<i32> = expenses.iter().copied().filter(|&e| e < 1010).collect();
+    let max = 1011 + (2 * size as u64).max(1009);
+
+    while expenses.len() < size {
+        let expense = rng.range(1011..max) as i32;
+        let rest = 2020 - expense;
+
+        if !expenses.contains(&expense)
+            && !small.contains(&rest)
+            && !small
+                .iter()
+                .tuple_combinations()
+                .any(|(a, b)| a + b == rest)
+        {
+            expenses.push(expense);
+        }
     }
 
-    #[bench]
-    fn bench_part_2(b: &mut Bencher) {
-        let expenses = parse(input()).unwrap();
+    rng.shuffle(&mut expenses);
+    expenses.iter().map(|e| format!("{}\n", e)).collect()
+}
 
-        b.iter(|| part_2(&expenses));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    fn input() -> Box<dyn BufRead> {
+        crate::input_file(1).unwrap()
     }
 
     #[test]
     fn part_1_edge_case() {
         let expenses = vec![1010];
 
-        assert!(part_1(&expenses) == None)
+        assert!(part_1(&expenses).is_none())
     }
 
     #[test]
     fn part_1_equiv_comb() {
         let expenses = parse(input()).unwrap();
 
-        assert!(part_1(&expenses) == _solve_combinations(&expenses, 2, 2020));
+        assert!(part_1(&expenses) == solve_combinations(&expenses, 2, 2020));
     }
 
     #[test]
     fn part_2_equiv_comb() {
         let expenses = parse(input()).unwrap();
 
-        assert!(part_2(&expenses) == _solve_combinations(&expenses, 3, 2020));
+        assert!(part_2(&expenses) == solve_combinations(&expenses, 3, 2020));
     }
 
-    #[bench]
-    fn bench_part_1_comb(b: &mut Bencher) {
-        let expenses = parse(input()).unwrap();
+    #[test]
+    fn k_sum_example() {
+        let expenses = [1721, 979, 366, 299, 675, 1456];
+
+        assert_eq!(k_sum(&expenses, 2, 2020), [[0, 3]]);
+        assert_eq!(k_sum(&expenses, 3, 2020), [[1, 2, 4]]);
+        assert_eq!(k_sum(&expenses, 1, 366), [[2]]);
+        assert_eq!(k_sum(&expenses, 0, 0), [[0; 0]]);
+        assert!(k_sum(&expenses, 7, 2020).is_empty());
+    }
 
-        b.iter(|| _solve_combinations(&expenses, 2, 2020));
+    #[test]
+    fn distinct_sums() {
+        let expenses = [1010, 2025, 1010, 0, 1010, -5, 2020, 1010];
+
+        assert_eq!(
+            distinct_k_sums(&expenses, 2, 2020),
+            [
+                (vec![-5, 2025], 1),
+                (vec![0, 2020], 1),
+                (vec![1010, 1010], 6)
+            ]
+        );
+        assert_eq!(
+            distinct_k_sums(&expenses, 3, 2020),
+            [(vec![-5, 0, 2025], 1), (vec![0, 1010, 1010], 6)]
+        );
+        assert_eq!(distinct_k_sums(&[1010; 5], 4, 4040), [(vec![1010; 4], 5)]);
+        assert!(distinct_k_sums(&[1010], 2, 2020).is_empty());
     }
 
-    #[bench]
-    fn bench_part_2_comb(b: &mut Bencher) {
-        let expenses = parse(input()).unwrap();
+    /// Products of all the `k` expenses summing up to 2020
+    fn products(expenses: &[i32], k: usize) -> Vec<i32> {
+        expenses
+            .iter()
+            .copied()
+            .combinations(k)
+            .filter(|v| v.iter().sum::<i32>() == 2020)
+            .map(|v| v.iter().product())
+            .collect()
+    }
+
+    /// Random expenses, with `k` of them summing up to 2020 planted among them
+    fn expenses(k: usize) -> impl Strategy<Value = Vec<i32>> {
+        let planted = prop::collection::vec(1..2020 / k as i32, k - 1).prop_map(|mut planted| {
+            planted.push(2020 - planted.iter().sum::<i32>());
+            planted
+        });
+
+        (planted, prop::collection::vec(1..2500, 0..40))
+            .prop_map(|(planted, others)| [planted, others].concat())
+            .prop_shuffle()
+    }
+
+    proptest! {
+        #[test]
+        fn k_sum_finds_all_combinations(
+            expenses in prop::collection::vec(-20..20, 0..14),
+            k in 0..6_usize,
+            goal in -30..30_i64,
+        ) {
+            let expected: Vec<Vec<usize>> = (0..expenses.len())
+                .combinations(k)
+                .filter(|indices| indices.iter().map(|&i| expenses[i] as i64).sum::<i64>() == goal)
+                .collect();
+
+            prop_assert_eq!(k_sum(&expenses, k, goal), expected);
+        }
 
-        b.iter(|| _solve_combinations(&expenses, 3, 2020));
+        #[test]
+        fn distinct_k_sums_group_k_sums(
+            expenses in prop::collection::vec(-5..5, 0..14),
+            k in 0..5_usize,
+            goal in -10..10_i64,
+        ) {
+            let tuples = k_sum(&expenses, k, goal);
+            let sums = distinct_k_sums(&expenses, k, goal);
+
+            let values = tuples
+                .iter()
+                .map(|indices| indices.iter().map(|&i| expenses[i]).sorted().collect::<Vec<_>>())
+                .unique()
+                .sorted()
+                .collect::<Vec<_>>();
+            prop_assert_eq!(sums.iter().map(|(v, _)| v.clone()).collect::<Vec<_>>(), values);
+            prop_assert_eq!(sums.iter().map(|(_, ways)| ways).sum::<u64>(), tuples.len() as u64);
+        }
+
+        #[test]
+        fn part_1_agrees_with_combinations(expenses in expenses(2)) {
+            let products = products(&expenses, 2);
+            let answer = part_1(&expenses).unwrap();
+
+            prop_assert!(products.contains(&answer));
+            if products.iter().all_equal() {
+                prop_assert_eq!(Some(answer), solve_combinations(&expenses, 2, 2020));
+            }
+        }
+
+        #[test]
+        fn part_2_agrees_with_combinations(expenses in expenses(3)) {
+            let products = products(&expenses, 3);
+            let answer = part_2(&expenses).unwrap();
+
+            prop_assert!(products.contains(&answer));
+            if products.iter().all_equal() {
+                prop_assert_eq!(Some(answer), solve_combinations(&expenses, 3, 2020));
+            }
+        }
     }
 }
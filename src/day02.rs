@@ -1,64 +1,136 @@
-use std::io;
-use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
+//! Day 2: Password Philosophy
 
+use std::fmt;
+use std::io::prelude::*;
+use std::iter::Peekable;
 use std::ops::BitXor;
+use std::str::FromStr;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let passwords = parse(input)?;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
 
-    let solution = match part {
-        1 => part_1(&passwords),
-        2 => part_2(&passwords),
-        _ => unimplemented!(),
-    };
+use crate::error::{column, Context};
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day02;
 
-    println!("{}", solution);
+impl Solution for Day02 {
+    const DAY: u8 = 2;
 
-    Ok(())
+    type Input = Vec<(Rule, Password)>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(passwords: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&passwords))
+    }
+
+    fn part_2(passwords: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&passwords))
+    }
 }
 
-type Password = Vec<u8>;
+impl Generate for Day02 {
+    const INPUT_SIZE: usize = 1000;
 
-#[derive(Debug)]
-struct Rule {
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+pub type Password = String;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
     min: u8,
     max: u8,
-    char: u8,
-}
-
-fn parse(input: impl BufRead) -> io::Result<Vec<(Rule, Password)>> {
-    input
-        .lines()
-        .map(|line| try {
-            let line = line?;
-            let (rule, password) = line.split_once(": ").unwrap();
-            let (range, char) = rule.split_once(' ').unwrap();
-            let (min, max) = range.split_once('-').unwrap();
-
-            let rule = Rule {
-                min: min
-                    .parse()
-                    .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?,
-                max: max
-                    .parse()
-                    .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?,
-                char: char.as_bytes()[0],
-            };
-
-            (rule, password.bytes().collect())
-        })
-        .collect()
+    /// A single grapheme
+    char: String,
 }
 
-fn char_count(char: u8, password: &Password) -> usize {
-    password.iter().filter(|&&c| c == char).count()
+/// What the positions and counts of the rules are in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Bytes, as in the puzzle, which only makes sense for ASCII passwords
+    Byte,
+    /// Unicode scalar values
+    Char,
+    /// Extended grapheme clusters, what is seen as a single character
+    Grapheme,
 }
 
-fn valid_part_1(rule: &Rule, password: &Password) -> bool {
-    let count = char_count(rule.char, password) as u8;
+impl Unit {
+    /// Units of the text, as slices of its bytes
+    pub fn split(self, text: &str) -> Vec<&[u8]> {
+        match self {
+            Unit::Byte => text.as_bytes().chunks(1).collect(),
+            Unit::Char => text
+                .char_indices()
+                .map(|(i, c)| &text.as_bytes()[i..i + c.len_utf8()])
+                .collect(),
+            Unit::Grapheme => text.graphemes(true).map(str::as_bytes).collect(),
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = Error;
 
-    rule.min <= count && count <= rule.max
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "byte" => Ok(Unit::Byte),
+            "char" => Ok(Unit::Char),
+            "grapheme" => Ok(Unit::Grapheme),
+            _ => Err(Error::parse(format!("Unknown unit: {}", s))),
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Result<(Rule, Password)> {
+    let (rule, password) = line
+        .split_once(": ")
+        .ok_or_else(|| Error::parse("Expected \": \" after the rule"))?;
+    let (range, char) = rule
+        .split_once(' ')
+        .ok_or_else(|| Error::parse("Expected a character after the range"))?;
+    let (min, max) = range
+        .split_once('-')
+        .ok_or_else(|| Error::parse("Expected a range like 1-3").at_column(1))?;
+
+    let rule = Rule {
+        min: min.parse().at_column(column(line, min))?,
+        max: max.parse().at_column(column(line, max))?,
+        char: match char.graphemes(true).count() {
+            1 => char.to_string(),
+            _ => {
+                return Err(
+                    Error::parse("Expected a single character").at_column(column(line, char))
+                )
+            }
+        },
+    };
+
+    Ok((rule, password.to_string()))
+}
+
+pub fn parse(input: impl BufRead) -> Result<Vec<(Rule, Password)>> {
+    records(input, parse_line).collect()
+}
+
+fn char_count(char: &str, units: &[&[u8]]) -> usize {
+    units.iter().filter(|&&c| c == char.as_bytes()).count()
+}
+
+fn valid_part_1(rule: &Rule, units: &[&[u8]]) -> bool {
+    let count = char_count(&rule.char, units);
+
+    rule.min as usize <= count && count <= rule.max as usize
 }
 
 fn count_valid<F>(passwords: &[(Rule, Password)], valid: F) -> usize
@@ -67,19 +139,537 @@ where
 {
     passwords
         .iter()
-        .filter(|&(rule, password)| valid(&rule, &password))
+        .filter(|&(rule, password)| valid(rule, password))
         .count()
 }
 
-fn part_1(passwords: &[(Rule, Password)]) -> usize {
-    count_valid(passwords, valid_part_1)
+pub fn part_1(passwords: &[(Rule, Password)]) -> usize {
+    part_1_in(Unit::Byte, passwords)
+}
+
+/// Part 1 with counts of `unit`s
+pub fn part_1_in(unit: Unit, passwords: &[(Rule, Password)]) -> usize {
+    count_valid(passwords, |rule, password| {
+        valid_part_1(rule, &unit.split(password))
+    })
+}
+
+/// Character at a position starting from 1
+fn char_at<'a>(units: &[&'a [u8]], position: u8) -> Option<&'a [u8]> {
+    let index = (position as usize).checked_sub(1)?;
+    units.get(index).copied()
+}
+
+fn valid_part_2(rule: &Rule, units: &[&[u8]]) -> bool {
+    let char = Some(rule.char.as_bytes());
+
+    (char_at(units, rule.min) == char).bitxor(char_at(units, rule.max) == char)
+}
+
+pub fn part_2(passwords: &[(Rule, Password)]) -> usize {
+    part_2_in(Unit::Byte, passwords)
+}
+
+/// Part 2 with positions in `unit`s
+pub fn part_2_in(unit: Unit, passwords: &[(Rule, Password)]) -> usize {
+    count_valid(passwords, |rule, password| {
+        valid_part_2(rule, &unit.split(password))
+    })
+}
+
+/// Clause of a password policy, checked against a password and its rule.
+///
+/// Policies are written like `all(count, min-length 8, any(class digit,
+/// class symbol), forbid password, forbid "1 2 3")`, which `Display` gives
+/// back. Texts with spaces, commas, parentheses or quotes are quoted, with `\`
+/// escaping quotes and backslashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Policy {
+    /// The character of the rule appears between its min and max times (part 1)
+    Count,
+    /// The character of the rule is at exactly one of the positions min and
+    /// max (part 2)
+    Positions,
+    /// At least this many characters (units)
+    MinLength(usize),
+    /// At least one character of the class
+    Class(CharClass),
+    /// Doesn't contain the text
+    Forbid(String),
+    /// All of the clauses hold
+    All(Vec<Policy>),
+    /// At least one of the clauses holds
+    Any(Vec<Policy>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    /// ASCII punctuation
+    Symbol,
+}
+
+impl CharClass {
+    pub fn contains(self, char: u8) -> bool {
+        match self {
+            CharClass::Lower => char.is_ascii_lowercase(),
+            CharClass::Upper => char.is_ascii_uppercase(),
+            CharClass::Digit => char.is_ascii_digit(),
+            CharClass::Symbol => char.is_ascii_punctuation(),
+        }
+    }
+}
+
+impl Policy {
+    /// Whether the password follows the policy, with positions, counts and
+    /// lengths in `unit`s
+    pub fn valid(&self, unit: Unit, rule: &Rule, password: &str) -> bool {
+        self.holds(rule, password, &unit.split(password))
+    }
+
+    fn holds(&self, rule: &Rule, password: &str, units: &[&[u8]]) -> bool {
+        match self {
+            Policy::Count => valid_part_1(rule, units),
+            Policy::Positions => valid_part_2(rule, units),
+            Policy::MinLength(len) => units.len() >= *len,
+            Policy::Class(class) => password.bytes().any(|c| class.contains(c)),
+            Policy::Forbid(text) => !password.contains(text.as_str()),
+            Policy::All(clauses) => clauses.iter().all(|c| c.holds(rule, password, units)),
+            Policy::Any(clauses) => clauses.iter().any(|c| c.holds(rule, password, units)),
+        }
+    }
+
+    /// Innermost clauses the password breaks, empty when it is valid. When
+    /// none of the clauses of an `any` holds, they are all reported.
+    pub fn failures(&self, unit: Unit, rule: &Rule, password: &str) -> Vec<&Policy> {
+        let mut failures = Vec::new();
+        self.collect_failures(rule, password, &unit.split(password), &mut failures);
+        failures
+    }
+
+    fn collect_failures<'a>(
+        &'a self,
+        rule: &Rule,
+        password: &str,
+        units: &[&[u8]],
+        failures: &mut Vec<&'a Policy>,
+    ) {
+        let before = failures.len();
+
+        match self {
+            Policy::All(clauses) => {
+                for clause in clauses {
+                    clause.collect_failures(rule, password, units, failures);
+                }
+            }
+            Policy::Any(clauses) => {
+                if !self.holds(rule, password, units) {
+                    for clause in clauses {
+                        clause.collect_failures(rule, password, units, failures);
+                    }
+
+                    // Without any clause
+                    if failures.len() == before {
+                        failures.push(self);
+                    }
+                }
+            }
+            _ => {
+                if !self.holds(rule, password, units) {
+                    failures.push(self);
+                }
+            }
+        }
+    }
+}
+
+/// Clauses of the policy which each password breaks, in the order of the input
+pub fn report<'a>(
+    policy: &'a Policy,
+    unit: Unit,
+    passwords: &[(Rule, Password)],
+) -> Vec<Vec<&'a Policy>> {
+    passwords
+        .iter()
+        .map(|(rule, password)| policy.failures(unit, rule, password))
+        .collect()
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CharClass::Lower => "lower",
+            CharClass::Upper => "upper",
+            CharClass::Digit => "digit",
+            CharClass::Symbol => "symbol",
+        })
+    }
+}
+
+impl FromStr for CharClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "lower" => Ok(CharClass::Lower),
+            "upper" => Ok(CharClass::Upper),
+            "digit" => Ok(CharClass::Digit),
+            "symbol" => Ok(CharClass::Symbol),
+            _ => Err(Error::parse(format!("Unknown character class: {}", s))),
+        }
+    }
 }
 
-fn valid_part_2(rule: &Rule, password: &Password) -> bool {
-    (password[rule.min as usize - 1] == rule.char)
-        .bitxor(password[rule.max as usize - 1] == rule.char)
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Policy::Count => write!(f, "count"),
+            Policy::Positions => write!(f, "positions"),
+            Policy::MinLength(len) => write!(f, "min-length {}", len),
+            Policy::Class(class) => write!(f, "class {}", class),
+            Policy::Forbid(text) if is_word(text) => write!(f, "forbid {}", text),
+            Policy::Forbid(text) => write!(
+                f,
+                "forbid \"{}\"",
+                text.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            Policy::All(clauses) | Policy::Any(clauses) => {
+                f.write_str(if let Policy::All(_) = self {
+                    "all("
+                } else {
+                    "any("
+                })?;
+                for (i, clause) in clauses.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", clause)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut tokens = tokens(s);
+        let policy = parse_policy(s, &mut tokens)?;
+
+        match tokens.next() {
+            Some(token) => {
+                Err(Error::parse("Expected the end of the policy").at_column(column(s, token)))
+            }
+            None => Ok(policy),
+        }
+    }
+}
+
+/// Whether the text can be written without quotes
+fn is_word(text: &str) -> bool {
+    !text.is_empty() && !text.contains(|c: char| c.is_whitespace() || "(),\"".contains(c))
+}
+
+/// Words, quoted texts and punctuation of a policy, as slices of it
+fn tokens(s: &str) -> Peekable<impl Iterator<Item = &str>> {
+    let mut rest = s;
+
+    std::iter::from_fn(move || {
+        rest = rest.trim_start();
+
+        let len = match rest.chars().next()? {
+            '(' | ')' | ',' => 1,
+            // Up to the closing quote, or the end when there is none
+            '"' => {
+                let mut escaped = false;
+                rest.char_indices()
+                    .skip(1)
+                    .find(|&(_, c)| {
+                        let end = !escaped && c == '"';
+                        escaped = !escaped && c == '\\';
+                        end
+                    })
+                    .map_or(rest.len(), |(i, _)| i + 1)
+            }
+            _ => rest
+                .find(|c: char| c.is_whitespace() || "(),".contains(c))
+                .unwrap_or(rest.len()),
+        };
+
+        let (token, tail) = rest.split_at(len);
+        rest = tail;
+        Some(token)
+    })
+    .peekable()
 }
 
-fn part_2(passwords: &[(Rule, Password)]) -> usize {
-    count_valid(passwords, valid_part_2)
+/// Next token, an error mentioning what was `expected` at the end
+fn next_token<'a>(
+    s: &'a str,
+    tokens: &mut impl Iterator<Item = &'a str>,
+    expected: &str,
+) -> Result<&'a str> {
+    tokens
+        .next()
+        .ok_or_else(|| Error::parse(format!("Expected {}", expected)).at_column(s.len() + 1))
+}
+
+/// Word following a clause
+fn argument<'a>(
+    s: &'a str,
+    tokens: &mut impl Iterator<Item = &'a str>,
+    expected: &str,
+) -> Result<&'a str> {
+    match next_token(s, tokens, expected)? {
+        token @ ("(" | ")" | ",") => {
+            Err(Error::parse(format!("Expected {}", expected)).at_column(column(s, token)))
+        }
+        word => Ok(word),
+    }
+}
+
+/// Text of a `forbid` clause, a word or a quoted text
+fn text<'a>(s: &'a str, tokens: &mut impl Iterator<Item = &'a str>) -> Result<String> {
+    let word = argument(s, tokens, "a text")?;
+    let quoted = match word.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => return Ok(word.to_string()),
+    };
+
+    let mut text = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // The token ends with the closing quote
+            '"' => return Ok(text),
+            '\\' => text.extend(chars.next()),
+            c => text.push(c),
+        }
+    }
+
+    Err(Error::parse("Expected a closing quote").at_column(s.len() + 1))
+}
+
+fn parse_policy<'a, I>(s: &'a str, tokens: &mut Peekable<I>) -> Result<Policy>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let word = next_token(s, tokens, "a policy")?;
+
+    let policy = match word {
+        "count" => Policy::Count,
+        "positions" => Policy::Positions,
+        "min-length" => {
+            let len = argument(s, tokens, "a length")?;
+            Policy::MinLength(len.parse().at_column(column(s, len))?)
+        }
+        "class" => {
+            let class = argument(s, tokens, "a character class")?;
+            Policy::Class(class.parse().at_column(column(s, class))?)
+        }
+        "forbid" => Policy::Forbid(text(s, tokens)?),
+        "all" | "any" => {
+            let open = next_token(s, tokens, "\"(\"")?;
+            if open != "(" {
+                return Err(Error::parse("Expected \"(\"").at_column(column(s, open)));
+            }
+
+            let mut clauses = Vec::new();
+            if tokens.next_if_eq(&")").is_none() {
+                loop {
+                    clauses.push(parse_policy(s, tokens)?);
+                    match next_token(s, tokens, "\",\" or \")\"")? {
+                        "," => {}
+                        ")" => break,
+                        token => {
+                            return Err(
+                                Error::parse("Expected \",\" or \")\"").at_column(column(s, token))
+                            )
+                        }
+                    }
+                }
+            }
+
+            if word == "all" {
+                Policy::All(clauses)
+            } else {
+                Policy::Any(clauses)
+            }
+        }
+        _ => {
+            return Err(Error::parse(format!("Unknown policy: {}", word)).at_column(column(s, word)))
+        }
+    };
+
+    Ok(policy)
+}
+
+/// `size` passwords with their rule, about a third of the characters of a
+/// password being the character of its rule
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let letter = |rng: &mut Rng| (b'a' + rng.range(0..26) as u8) as char;
+
+    (0..size)
+        .map(|_| {
+            let min = rng.range(1..11);
+            let max = rng.range(min + 1..min + 11);
+            let char = letter(rng);
+
+            let password: String = (0..rng.range(max..max + 6))
+                .map(|_| if rng.ratio(1, 3) { char } else { letter(rng) })
+                .collect();
+
+            format!("{}-{} {}: {}\n", min, max, char, password)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n";
+
+    #[test]
+    fn policies() {
+        let passwords = parse(EXAMPLE.as_bytes()).unwrap();
+        let count = |policy: &str| {
+            let policy: Policy = policy.parse().unwrap();
+            count_valid(&passwords, |rule, password| {
+                policy.valid(Unit::Byte, rule, password)
+            })
+        };
+
+        assert_eq!(count("count"), part_1(&passwords));
+        assert_eq!(count("positions"), part_2(&passwords));
+        assert_eq!(count("all(count, positions)"), 1);
+        assert_eq!(count("any(count, positions)"), 2);
+        assert_eq!(count("min-length 6"), 1);
+        assert_eq!(count("class upper"), 0);
+        assert_eq!(count("forbid cd"), 1);
+    }
+
+    #[test]
+    fn failures() {
+        let passwords = parse(EXAMPLE.as_bytes()).unwrap();
+        let policy: Policy = "all(count, any(class digit, min-length 6), forbid cd)"
+            .parse()
+            .unwrap();
+
+        let report: Vec<Vec<String>> = report(&policy, Unit::Byte, &passwords)
+            .iter()
+            .map(|failures| failures.iter().map(|p| p.to_string()).collect())
+            .collect();
+        assert_eq!(
+            report,
+            [
+                vec!["class digit", "min-length 6", "forbid cd"],
+                vec!["count", "class digit", "min-length 6", "forbid cd"],
+                vec![],
+            ]
+        );
+
+        let empty = Policy::Any(vec![]);
+        assert_eq!(
+            empty.failures(Unit::Byte, &passwords[0].0, &passwords[0].1),
+            [&empty]
+        );
+    }
+
+    #[test]
+    fn unicode() {
+        // "e" with a combining acute accent is a single grapheme of 2 chars
+        let input = "1-2 é: héllo\n2-3 ü: aüü\n1-3 e\u{301}: e\u{301}xx\n";
+        let passwords = parse(input.as_bytes()).unwrap();
+
+        assert_eq!(part_1(&passwords), 0);
+        assert_eq!(part_1_in(Unit::Char, &passwords), 2);
+        assert_eq!(part_1_in(Unit::Grapheme, &passwords), 3);
+
+        assert_eq!(part_2(&passwords), 0);
+        assert_eq!(part_2_in(Unit::Char, &passwords), 1);
+        assert_eq!(part_2_in(Unit::Grapheme, &passwords), 2);
+
+        // ASCII passwords are the same in every unit
+        let passwords = parse(EXAMPLE.as_bytes()).unwrap();
+        for &unit in &[Unit::Char, Unit::Grapheme] {
+            assert_eq!(part_1_in(unit, &passwords), part_1(&passwords));
+            assert_eq!(part_2_in(unit, &passwords), part_2(&passwords));
+        }
+
+        let policy = Policy::MinLength(3);
+        let rule = &parse("1-1 x: x".as_bytes()).unwrap()[0].0;
+        assert!(policy.valid(Unit::Byte, rule, "ñé"));
+        assert!(!policy.valid(Unit::Char, rule, "ñé"));
+
+        assert!(parse("1-3 ab: abc".as_bytes()).is_err());
+        assert_eq!("grapheme".parse::<Unit>().unwrap(), Unit::Grapheme);
+        assert!("word".parse::<Unit>().is_err());
+    }
+
+    #[test]
+    fn policy_syntax() {
+        let text = "all(count, any(class digit, class symbol), min-length 8, forbid abc)";
+        let policy: Policy = text.parse().unwrap();
+
+        assert_eq!(policy.to_string(), text);
+        assert_eq!(
+            "any( positions ,count )"
+                .parse::<Policy>()
+                .unwrap()
+                .to_string(),
+            "any(positions, count)"
+        );
+
+        let error = |text: &str| text.parse::<Policy>().unwrap_err().to_string();
+        assert!(error("").contains("Expected a policy"));
+        assert!(error("all(count").contains("column 10"));
+        assert!(error("any count").contains("column 5"));
+        assert!(error("class vowel").contains("Unknown character class"));
+        assert!(error("min-length").contains("Expected a length"));
+        assert!(error("forbid )").contains("column 8"));
+        assert!(error("count count").contains("Expected the end"));
+        assert!(error("size 3").contains("Unknown policy"));
+        assert!(error("forbid \"abc").contains("Expected a closing quote"));
+    }
+
+    #[test]
+    fn policy_round_trip() {
+        let texts = [
+            "abc",
+            "a b",
+            "a,b",
+            "(a)",
+            "say \"hi\"",
+            "a\\b",
+            "\\",
+            "",
+            "\"",
+            "é ü",
+        ];
+        for &text in texts.iter() {
+            let policy = Policy::All(vec![Policy::Forbid(text.to_string()), Policy::Any(vec![])]);
+            assert_eq!(policy.to_string().parse::<Policy>().unwrap(), policy);
+        }
+
+        let text = r#"any(forbid "a, b", forbid a"b, forbid "\\\"", all())"#;
+        let policy: Policy = text.parse().unwrap();
+        assert_eq!(
+            policy,
+            Policy::Any(vec![
+                Policy::Forbid("a, b".to_string()),
+                Policy::Forbid("a\"b".to_string()),
+                Policy::Forbid("\\\"".to_string()),
+                Policy::All(vec![]),
+            ])
+        );
+        assert_eq!(
+            policy.to_string(),
+            r#"any(forbid "a, b", forbid "a\"b", forbid "\\\"", all())"#
+        );
+    }
 }
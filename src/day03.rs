@@ -1,49 +1,252 @@
-use std::io;
+//! Day 3: Toboggan Trajectory
+
+use std::cmp::Reverse;
 use std::io::prelude::*;
+use std::ops::RangeInclusive;
+
+use crate::grid::{Cell, Grid, Pos};
+use crate::rng::Rng;
+use crate::{Generate, Result, Solution};
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    const DAY: u8 = 3;
+
+    type Input = Forest;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(forest: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&forest))
+    }
+
+    fn part_2(forest: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&forest))
+    }
+}
+
+impl Generate for Day03 {
+    const INPUT_SIZE: usize = 323;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let forest = parse(input)?;
+pub type Forest = Grid<bool>;
 
-    let solution = match part {
-        1 => part_1(&forest),
-        2 => part_2(&forest),
-        _ => unimplemented!(),
+pub fn parse(input: impl BufRead) -> Result<Forest> {
+    Grid::parse(input)
+}
+
+/// Squares to move right (left when negative) and down at each step
+pub type Slope = (isize, usize);
+
+/// Squares visited going down the slope from the top left corner to the
+/// bottom, the forest repeating to the right and to the left. A slope which
+/// doesn't go down never gets there, and has no path.
+fn positions(forest: &Forest, (dx, dy): Slope) -> impl Iterator<Item = Pos> {
+    let width = forest.width();
+    let height = if dy == 0 || width == 0 {
+        0
+    } else {
+        forest.height()
+    };
+    let step = if width == 0 {
+        0
+    } else {
+        dx.rem_euclid(width as isize) as usize
     };
 
-    println!("{}", solution);
+    (0..height)
+        .step_by(dy.max(1))
+        .enumerate()
+        .map(move |(i, y)| (i * step % width, y))
+}
 
-    Ok(())
+/// Squares visited going down the slope, with `x` within the forest
+pub fn path(forest: &Forest, slope: Slope) -> Vec<Pos> {
+    positions(forest, slope).collect()
 }
 
-type Forest = Vec<Vec<bool>>;
+pub fn trees_in_slope(forest: &Forest, slope: Slope) -> usize {
+    positions(forest, slope).filter(|&pos| forest[pos]).count()
+}
 
-fn parse(input: impl BufRead) -> io::Result<Forest> {
-    input
-        .lines()
-        .map(|line| try { line?.bytes().map(|b| b == b'#').collect() })
+/// Trees on each slope with `dx` and `dy` within the bounds (except a `dy` of
+/// 0), by `dx` and then `dy`
+pub fn slopes(
+    forest: &Forest,
+    dxs: RangeInclusive<isize>,
+    dys: RangeInclusive<usize>,
+) -> Vec<(Slope, usize)> {
+    dxs.flat_map(|dx| dys.clone().filter(|&dy| dy > 0).map(move |dy| (dx, dy)))
+        .map(|slope| (slope, trees_in_slope(forest, slope)))
         .collect()
 }
 
-fn trees_in_slope(forest: &Forest, dx: usize, dy: usize) -> usize {
-    forest
-        .iter()
-        .step_by(dy)
-        .enumerate()
-        .filter(|(i, row)| {
-            let x = i * dx % row.len();
-            row[x]
-        })
-        .count()
+/// Slope within the bounds with the fewest trees (the first of them), `None`
+/// without any slope
+pub fn fewest_trees(
+    forest: &Forest,
+    dxs: RangeInclusive<isize>,
+    dys: RangeInclusive<usize>,
+) -> Option<(Slope, usize)> {
+    slopes(forest, dxs, dys)
+        .into_iter()
+        .min_by_key(|&(_, trees)| trees)
+}
+
+/// Slope within the bounds with the most trees (the first of them), `None`
+/// without any slope
+pub fn most_trees(
+    forest: &Forest,
+    dxs: RangeInclusive<isize>,
+    dys: RangeInclusive<usize>,
+) -> Option<(Slope, usize)> {
+    slopes(forest, dxs, dys)
+        .into_iter()
+        .min_by_key(|&(_, trees)| Reverse(trees))
+}
+
+pub fn part_1(forest: &Forest) -> usize {
+    trees_in_slope(forest, (3, 1))
 }
 
-fn part_1(forest: &Forest) -> usize {
-    trees_in_slope(forest, 3, 1)
+pub fn part_2(forest: &Forest) -> usize {
+    trees_in_slope(forest, (1, 1))
+        * trees_in_slope(forest, (3, 1))
+        * trees_in_slope(forest, (5, 1))
+        * trees_in_slope(forest, (7, 1))
+        * trees_in_slope(forest, (1, 2))
 }
 
-fn part_2(forest: &Forest) -> usize {
-    trees_in_slope(forest, 1, 1)
-        * trees_in_slope(forest, 3, 1)
-        * trees_in_slope(forest, 5, 1)
-        * trees_in_slope(forest, 7, 1)
-        * trees_in_slope(forest, 1, 2)
+/// Square of a forest with a path over it, drawn like in the puzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Square {
+    Open,
+    Tree,
+    /// Open square on the path
+    Visited,
+    /// Tree on the path
+    Hit,
+}
+
+impl Cell for Square {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            b'.' => Some(Square::Open),
+            b'#' => Some(Square::Tree),
+            b'O' => Some(Square::Visited),
+            b'X' => Some(Square::Hit),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Square::Open => '.',
+            Square::Tree => '#',
+            Square::Visited => 'O',
+            Square::Hit => 'X',
+        }
+    }
+}
+
+/// Forest with the path over it, which `Display` draws with `O` on the open
+/// squares of the path and `X` on its trees
+pub fn trace(forest: &Forest, path: &[Pos]) -> Grid<Square> {
+    let mut squares = forest.map(|_, &tree| if tree { Square::Tree } else { Square::Open });
+
+    for &pos in path {
+        if let Some(square) = squares.get_mut(pos) {
+            *square = match square {
+                Square::Tree | Square::Hit => Square::Hit,
+                Square::Open | Square::Visited => Square::Visited,
+            };
+        }
+    }
+
+    squares
+}
+
+/// Forest of `size` rows of 31 squares, with a tree on about a quarter of them
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let trees = (0..31 * size.max(1)).map(|_| rng.ratio(1, 4)).collect();
+
+    Forest::new(31, trees).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#
+";
+
+    #[test]
+    fn example() {
+        let forest = parse(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(part_1(&forest), 7);
+        assert_eq!(part_2(&forest), 336);
+    }
+
+    #[test]
+    fn paths() {
+        let forest = parse(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(path(&forest, (1, 2))[..3], [(0, 0), (1, 2), (2, 4)]);
+        assert_eq!(path(&forest, (-2, 5)), [(0, 0), (9, 5), (7, 10)]);
+        assert!(path(&forest, (1, 0)).is_empty());
+
+        // Going left is going right around the forest
+        for dx in 1..11 {
+            assert_eq!(
+                trees_in_slope(&forest, (-dx, 1)),
+                trees_in_slope(&forest, (11 - dx, 1))
+            );
+        }
+
+        let trace = trace(&forest, &path(&forest, (3, 1))).to_string();
+        assert!(trace.starts_with("O.##.......\n#..O#...#..\n.#....X..#.\n"));
+        assert_eq!(trace.matches('X').count(), 7);
+        assert_eq!(trace.matches('O').count(), 4);
+    }
+
+    #[test]
+    fn traces_parse_back() {
+        let forest = parse(EXAMPLE.as_bytes()).unwrap();
+        let squares = trace(&forest, &path(&forest, (3, 1)));
+
+        let parsed = Grid::<Square>::parse(squares.to_string().as_bytes()).unwrap();
+        assert_eq!(parsed, squares);
+        assert!(Grid::<Square>::parse("O.\nX?\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn search() {
+        let forest = parse(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(slopes(&forest, 1..=7, 1..=1).len(), 7);
+        assert_eq!(most_trees(&forest, 1..=7, 1..=2), Some(((3, 1), 7)));
+        assert_eq!(fewest_trees(&forest, 1..=7, 1..=2), Some(((5, 2), 0)));
+        assert_eq!(fewest_trees(&forest, 1..=7, 0..=0), None);
+    }
 }
@@ -1,24 +1,47 @@
-use std::io;
+//! Day 4: Passport Processing
+
 use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
 use std::num::ParseIntError;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let passport_builders = parse(input)?;
+use serde::{Deserialize, Serialize};
 
-    let solution = match part {
-        1 => part_1(&passport_builders),
-        2 => part_2(&passport_builders),
-        _ => unimplemented!(),
-    };
+use crate::error::{column, Context};
+use crate::parsing::{blocks, key_value};
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    const DAY: u8 = 4;
 
-    println!("{}", solution);
+    type Input = Vec<PassportBuilder>;
+    type Part1 = usize;
+    type Part2 = usize;
 
-    Ok(())
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(passport_builders: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&passport_builders))
+    }
+
+    fn part_2(passport_builders: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&passport_builders))
+    }
 }
 
-#[derive(Clone, Debug)]
-enum Height {
+impl Generate for Day04 {
+    const INPUT_SIZE: usize = 280;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Height {
     Cm(usize),
     In(usize),
     NotSpecified(usize),
@@ -26,31 +49,32 @@ enum Height {
 
 impl Height {
     fn parse(value: &str) -> Result<Self, ParseIntError> {
-        let hgt = &value[0..value.len() - 2];
-        let unit = &value[value.len() - 2..value.len()];
-
-        Ok(match unit {
-            "cm" => Height::Cm(hgt.parse()?),
-            "in" => Height::In(hgt.parse()?),
-            _ => Height::NotSpecified(value.parse()?),
+        Ok(if let Some(hgt) = value.strip_suffix("cm") {
+            Height::Cm(hgt.parse()?)
+        } else if let Some(hgt) = value.strip_suffix("in") {
+            Height::In(hgt.parse()?)
+        } else {
+            Height::NotSpecified(value.parse()?)
         })
     }
 }
 
+/// Passport with all its required fields
 #[derive(Clone, Debug)]
-struct Passport {
-    byr: usize,
-    iyr: usize,
-    eyr: usize,
-    hgt: Height,
-    hcl: String,
-    ecl: String,
-    pid: String,
-    cid: Option<usize>,
+pub struct Passport {
+    pub byr: usize,
+    pub iyr: usize,
+    pub eyr: usize,
+    pub hgt: Height,
+    pub hcl: String,
+    pub ecl: String,
+    pub pid: String,
+    pub cid: Option<usize>,
 }
 
 impl Passport {
-    fn valid(&self) -> bool {
+    /// Whether the values of the fields are valid
+    pub fn valid(&self) -> bool {
         if !matches!(self.byr, 1920..=2002) {
             return false;
         }
@@ -101,8 +125,8 @@ impl Passport {
     }
 }
 
-#[derive(Clone, Debug)]
-struct PassportBuilder {
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PassportBuilder {
     byr: Option<usize>,
     iyr: Option<usize>,
     eyr: Option<usize>,
@@ -127,9 +151,8 @@ impl PassportBuilder {
         }
     }
 
-    fn parse(&mut self, key_pair: &str) -> Result<(), ParseIntError> {
-        let key = &key_pair[0..3];
-        let value = &key_pair[4..key_pair.len()];
+    fn parse(&mut self, key_pair: &str) -> Result<()> {
+        let (key, value) = key_value(key_pair, ":")?;
 
         match key {
             "byr" => self.byr = Some(value.parse()?),
@@ -140,13 +163,14 @@ impl PassportBuilder {
             "ecl" => self.ecl = Some(value.to_string()),
             "pid" => self.pid = Some(value.to_string()),
             "cid" => self.cid = Some(value.parse()?),
-            _ => unreachable!(), // TODO Better error reporting?
+            _ => return Err(Error::parse(format!("Unknown key {:?}", key))),
         }
 
         Ok(())
     }
 
-    fn build(&self) -> Option<Passport> {
+    /// Passport, if none of the required fields are missing
+    pub fn build(&self) -> Option<Passport> {
         Some(Passport {
             byr: self.byr?,
             iyr: self.iyr?,
@@ -160,37 +184,32 @@ impl PassportBuilder {
     }
 }
 
-fn parse(input: impl BufRead) -> io::Result<Vec<PassportBuilder>> {
-    let mut pb = PassportBuilder::new();
+pub fn parse(input: impl BufRead) -> Result<Vec<PassportBuilder>> {
     let mut passport_builders = Vec::new();
 
-    for line in input.lines() {
-        let line = line?;
-        if line.is_empty() {
-            passport_builders.push(pb.clone());
-            pb = PassportBuilder::new();
-        } else {
+    for block in blocks(input) {
+        let mut pb = PassportBuilder::new();
+
+        for (n, line) in block?.lines() {
             for key_pair in line.split_whitespace() {
-                pb.parse(key_pair)
-                    .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+                pb.parse(key_pair).at(n, column(line, key_pair))?;
             }
         }
-    }
 
-    // Push the last one
-    passport_builders.push(pb.clone());
+        passport_builders.push(pb);
+    }
 
     Ok(passport_builders)
 }
 
-fn part_1(passport_builders: &[PassportBuilder]) -> usize {
+pub fn part_1(passport_builders: &[PassportBuilder]) -> usize {
     passport_builders
         .iter()
         .filter(|&pb| pb.build().is_some())
         .count()
 }
 
-fn part_2(passport_builders: &[PassportBuilder]) -> usize {
+pub fn part_2(passport_builders: &[PassportBuilder]) -> usize {
     passport_builders
         .iter()
         .filter_map(|pb| pb.build())
@@ -198,11 +217,107 @@ fn part_2(passport_builders: &[PassportBuilder]) -> usize {
         .count()
 }
 
+/// `size` passports, some of them missing fields or with invalid values
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let passports: Vec<String> = (0..size).map(|_| generate_passport(rng)).collect();
+
+    passports.join("\n")
+}
+
+fn generate_passport(rng: &mut Rng) -> String {
+    let digits = |rng: &mut Rng, n| -> String {
+        (0..n)
+            .map(|_| (b'0' + rng.range(0..10) as u8) as char)
+            .collect()
+    };
+
+    let mut fields = Vec::new();
+
+    for &key in &["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"] {
+        let present = if key == "cid" {
+            rng.ratio(1, 2)
+        } else {
+            rng.ratio(9, 10)
+        };
+
+        if !present {
+            continue;
+        }
+
+        let valid = rng.ratio(4, 5);
+
+        let value = match (key, valid) {
+            ("byr", true) => rng.range(1920..2003).to_string(),
+            ("byr", false) => rng.range(1890..1920).to_string(),
+            ("iyr", true) => rng.range(2010..2021).to_string(),
+            ("iyr", false) => rng.range(2021..2030).to_string(),
+            ("eyr", true) => rng.range(2020..2031).to_string(),
+            ("eyr", false) => rng.range(2000..2020).to_string(),
+            ("hgt", true) if rng.ratio(1, 2) => format!("{}cm", rng.range(150..194)),
+            ("hgt", true) => format!("{}in", rng.range(59..77)),
+            ("hgt", false) => rng.range(59..194).to_string(),
+            ("hcl", valid) => {
+                let hex: String = (0..6)
+                    .map(|_| *rng.choose(b"0123456789abcdef") as char)
+                    .collect();
+                if valid {
+                    format!("#{}", hex)
+                } else {
+                    hex
+                }
+            }
+            ("ecl", true) => rng
+                .choose(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"])
+                .to_string(),
+            ("ecl", false) => rng.choose(&["gmt", "utc", "xry", "zzz"]).to_string(),
+            ("pid", true) => digits(rng, 9),
+            ("pid", false) => {
+                let len = *rng.choose(&[8, 10]);
+                digits(rng, len)
+            }
+            _ => rng.range(1..350).to_string(),
+        };
+
+        fields.push(format!("{}:{}", key, value));
+    }
+
+    rng.shuffle(&mut fields);
+
+    let mut passport = String::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let separator = if i + 1 == fields.len() || rng.ratio(1, 3) {
+            '\n'
+        } else {
+            ' '
+        };
+
+        passport.push_str(field);
+        passport.push(separator);
+    }
+
+    passport
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Cursor;
 
+    #[test]
+    fn parse_errors() {
+        let error = parse(Cursor::new("ecl:gry\npid:1 hgt:cm")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 7: cannot parse integer from empty string"
+        );
+
+        let error = parse(Cursor::new("ecl:gry foo:bar")).unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 9: Unknown key \"foo\"");
+
+        assert!(parse(Cursor::new("ecl")).is_err());
+    }
+
     #[test]
     fn test_part_1() {
         let input = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
@@ -1,26 +1,47 @@
-use std::io;
+//! Day 5: Binary Boarding
+
 use std::io::prelude::*;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let seats = parse(input).unwrap();
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    const DAY: u8 = 5;
+
+    type Input = Vec<Seat>;
+    type Part1 = Seat;
+    type Part2 = Seat;
 
-    let solution = match part {
-        1 => part_1(&seats),
-        2 => part_2_naive(seats),
-        _ => unimplemented!(),
-    };
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    println!("{}", solution.unwrap());
+    fn part_1(seats: Self::Input) -> Result<Self::Part1> {
+        part_1(&seats).ok_or(Error::NoSolution)
+    }
 
-    Ok(())
+    fn part_2(seats: Self::Input) -> Result<Self::Part2> {
+        part_2_naive(seats).ok_or(Error::NoSolution)
+    }
 }
 
-type Seat = usize;
+impl Generate for Day05 {
+    const INPUT_SIZE: usize = 800;
 
-fn parse_seat(input: &[u8]) -> Option<Seat> {
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+pub type Seat = usize;
+
+fn parse_seat(input: &[u8]) -> Result<Seat> {
     let mut seat = 0;
 
-    for &byte in input {
+    for (i, &byte) in input.iter().enumerate() {
         seat <<= 1;
 
         match byte {
@@ -28,31 +49,31 @@ fn parse_seat(input: &[u8]) -> Option<Seat> {
             b'B' => seat |= 1,
             b'L' => (),
             b'R' => seat |= 1,
-            _ => return None,
+            _ => {
+                let message = format!("Unexpected character {:?}", byte as char);
+                return Err(Error::parse(message).at_column(i + 1));
+            }
         }
     }
 
-    Some(seat)
+    Ok(seat)
 }
 
-fn parse(input: impl BufRead) -> Option<Vec<Seat>> {
-    input
-        .lines()
-        .map(|l| try { parse_seat(&l.ok()?.as_bytes())? })
-        .collect()
+pub fn parse(input: impl BufRead) -> Result<Vec<Seat>> {
+    records(input, |l| parse_seat(l.as_bytes())).collect()
 }
 
-fn part_1(seats: &[Seat]) -> Option<Seat> {
+pub fn part_1(seats: &[Seat]) -> Option<Seat> {
     seats.iter().copied().max()
 }
 
-fn part_2_naive(mut seats: Vec<Seat>) -> Option<Seat> {
+pub fn part_2_naive(mut seats: Vec<Seat>) -> Option<Seat> {
     seats.sort();
 
     let mut current = seats.pop()?;
 
     while let Some(next) = seats.pop() {
-        if next == current - 2 {
+        if next + 2 == current {
             return Some(current - 1);
         }
 
@@ -62,55 +83,131 @@ fn part_2_naive(mut seats: Vec<Seat>) -> Option<Seat> {
     None
 }
 
-fn _part_2_one_pass(seats: &str) -> Seat {
+/// Part 2 in a single pass over the seats, without sorting them: the missing
+/// seat is what their sum lacks to be the sum of all the seats from the lowest
+/// to the highest. `None` when that isn't a seat between them.
+pub fn part_2_one_pass(seats: &[Seat]) -> Option<Seat> {
+    if seats.is_empty() {
+        return None;
+    }
+
     let mut min = usize::MAX;
     let mut max = usize::MIN;
-    let mut sum = 0;
-
-    for seat in seats.as_bytes().chunks(11) {
-        let seat = parse_seat(&seat[0..10]).unwrap();
+    let mut sum: Seat = 0;
 
+    for &seat in seats {
         min = seat.min(min);
         max = seat.max(max);
-        sum += seat;
+        sum = sum.checked_add(seat)?;
     }
 
-    let total_sum = max * (max + 1) / 2 - min * (min - 1) / 2;
+    // Sum of the seats from `min` to `max`, without the ones below `min`
+    let total_sum = max.checked_mul(max.checked_add(1)?)? / 2 - min * min.saturating_sub(1) / 2;
 
-    total_sum - sum
+    total_sum
+        .checked_sub(sum)
+        .filter(|&seat| min < seat && seat < max)
+}
+
+/// Boarding passes of `size` consecutive seats but one, in a random order
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.clamp(3, 1000) as u64;
+    let min = rng.range(1..1023 - size) as Seat;
+    let missing = min + rng.range(1..size - 1) as Seat;
+
+    let mut seats: Vec<Seat> = (min..min + size as Seat)
+        .filter(|&seat| seat != missing)
+        .collect();
+    rng.shuffle(&mut seats);
+
+    seats
+        .iter()
+        .map(|seat| {
+            let mut pass: String = (0..10)
+                .rev()
+                .map(|bit| match (bit, seat >> bit & 1) {
+                    (3..=9, 0) => 'F',
+                    (3..=9, _) => 'B',
+                    (_, 0) => 'L',
+                    _ => 'R',
+                })
+                .collect();
+            pass.push('\n');
+            pass
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test::Bencher;
-
-    #[bench]
-    fn bench_part_2_naive(b: &mut Bencher) {
-        let mut input = aoc2020::input_file(5).unwrap();
-        let mut input_str = String::new();
-        input.read_to_string(&mut input_str).unwrap();
-
-        b.iter(|| {
-            let input = io::Cursor::new(&input_str);
-            let seats = parse(input).unwrap();
-            part_2_naive(seats.clone())
-        });
-    }
+    use proptest::prelude::*;
 
     #[test]
     fn part_2_equiv() {
-        let seats = parse(aoc2020::input_file(5).unwrap()).unwrap();
-        let input = include_str!("../inputs/day_05.txt");
+        let seats = parse(crate::input_file(5).unwrap()).unwrap();
+
+        let res = part_2_one_pass(&seats);
+        assert_eq!(part_2_naive(seats), res)
+    }
+
+    #[test]
+    fn first_seat() {
+        let passes = "FFFFFFFLLL\nFFFFFFFLLR\nFFFFFFFLRR\n";
+        let seats = parse(passes.as_bytes()).unwrap();
 
-        let res = _part_2_one_pass(&input);
-        assert_eq!(part_2_naive(seats).unwrap(), res)
+        assert_eq!(part_2_one_pass(&seats), Some(2));
+        assert_eq!(part_2_naive(seats), Some(2));
     }
 
-    #[bench]
-    fn bench_part_2_one_pass(b: &mut Bencher) {
-        let input = include_str!("../inputs/day_05.txt");
+    #[test]
+    fn no_missing_seat() {
+        assert_eq!(part_2_one_pass(&[]), None);
+        assert_eq!(part_2_one_pass(&[4, 5, 6]), None);
+        assert_eq!(part_2_one_pass(&[0, 1]), None);
+        assert_eq!(part_2_one_pass(&[usize::MAX, 1]), None);
+        assert_eq!(part_2_naive(vec![4, 5, 6]), None);
+    }
 
-        b.iter(|| _part_2_one_pass(&input));
+    /// Boarding passes of all the seats from `min` to `max`, but `missing`, in any order
+    fn boarding_passes() -> impl Strategy<Value = (String, Seat)> {
+        (0..1000usize, 2..200usize)
+            .prop_flat_map(|(min, len)| {
+                let max = (min + len).min(1023);
+                (Just(min), Just(max), min + 1..max)
+            })
+            .prop_flat_map(|(min, max, missing)| {
+                let seats: Vec<Seat> = (min..=max).filter(|&s| s != missing).collect();
+                (Just(seats).prop_shuffle(), Just(missing))
+            })
+            .prop_map(|(seats, missing)| {
+                let passes = seats
+                    .iter()
+                    .map(|seat| {
+                        (0..10)
+                            .rev()
+                            .map(|bit| match (bit, seat >> bit & 1) {
+                                (3..=9, 0) => 'F',
+                                (3..=9, _) => 'B',
+                                (_, 0) => 'L',
+                                _ => 'R',
+                            })
+                            .collect::<String>()
+                            + "\n"
+                    })
+                    .collect();
+
+                (passes, missing)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn part_2_implementations_agree((passes, missing) in boarding_passes()) {
+            let seats = parse(passes.as_bytes()).unwrap();
+
+            prop_assert_eq!(part_2_one_pass(&seats), Some(missing));
+            prop_assert_eq!(part_2_naive(seats), Some(missing));
+        }
     }
 }
@@ -1,43 +1,50 @@
-use std::io;
+//! Day 6: Custom Customs
+
 use std::io::prelude::*;
 
 use std::collections::BTreeSet;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let expenses = parse(input)?;
+use crate::parsing::blocks;
+use crate::rng::Rng;
+use crate::{Generate, Result, Solution};
 
-    let solution = match part {
-        1 => part_1(&expenses),
-        2 => part_2(&expenses),
-        _ => unimplemented!(),
-    };
+pub struct Day06;
 
-    println!("{}", solution);
+impl Solution for Day06 {
+    const DAY: u8 = 6;
 
-    Ok(())
-}
+    type Input = Vec<Group>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-type Answers = BTreeSet<char>;
-type Group = Vec<Answers>;
+    fn part_1(groups: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&groups))
+    }
 
-fn parse(input: impl BufRead) -> io::Result<Vec<Group>> {
-    let mut groups = Vec::new();
-    let mut group = Vec::new();
+    fn part_2(groups: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&groups))
+    }
+}
 
-    for line in input.lines() {
-        let line = line?;
+impl Generate for Day06 {
+    const INPUT_SIZE: usize = 500;
 
-        if line.is_empty() {
-            groups.push(group);
-            group = Vec::new();
-        } else {
-            group.push(line.chars().collect());
-        }
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
     }
+}
 
-    groups.push(group);
+pub type Answers = BTreeSet<char>;
+pub type Group = Vec<Answers>;
 
-    Ok(groups)
+pub fn parse(input: impl BufRead) -> Result<Vec<Group>> {
+    blocks(input)
+        .map(|block| Ok(block?.lines.iter().map(|l| l.chars().collect()).collect()))
+        .collect()
 }
 
 fn get_group_answers<F>(groups: &[Group], f: F) -> usize
@@ -46,18 +53,52 @@ where
 {
     groups
         .iter()
-        .map(|group| group.iter().cloned().fold_first(f.clone()).unwrap().len())
+        .map(|group| {
+            group
+                .iter()
+                .cloned()
+                .reduce(f.clone())
+                .map_or(0, |answers| answers.len())
+        })
         .sum()
 }
 
-fn part_1(groups: &[Group]) -> usize {
+pub fn part_1(groups: &[Group]) -> usize {
     get_group_answers(groups, |group, answers| {
         group.union(&answers).copied().collect()
     })
 }
 
-fn part_2(groups: &[Group]) -> usize {
+pub fn part_2(groups: &[Group]) -> usize {
     get_group_answers(groups, |group, answers| {
         group.intersection(&answers).copied().collect()
     })
 }
+
+/// `size` groups of up to 5 people, who have some answers in common
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut letters: Vec<char> = ('a'..='z').collect();
+
+    let groups: Vec<String> = (0..size)
+        .map(|_| {
+            rng.shuffle(&mut letters);
+            let common = rng.index(6);
+
+            (0..rng.range(1..6))
+                .map(|_| {
+                    let mut answers = letters[..common].to_vec();
+                    answers.extend(letters[common..].iter().filter(|_| rng.ratio(1, 5)));
+
+                    if answers.is_empty() {
+                        answers.push(letters[common]);
+                    }
+
+                    rng.shuffle(&mut answers);
+                    answers.into_iter().chain(Some('\n')).collect::<String>()
+                })
+                .collect()
+        })
+        .collect();
+
+    groups.join("\n")
+}
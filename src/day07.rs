@@ -1,31 +1,53 @@
-use std::io;
+//! Day 7: Handy Haversacks
+
 use std::io::prelude::*;
 
 use std::collections::vec_deque::VecDeque;
 use std::collections::{HashMap, HashSet};
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let rules = parse(input).unwrap();
+use crate::error::{column, Context};
+use crate::parsing::lines;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day07;
+
+impl Solution for Day07 {
+    const DAY: u8 = 7;
+
+    type Input = Rules;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(rules: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&rules))
+    }
 
-    let solution = match part {
-        1 => part_1(&rules),
-        2 => part_2(&rules),
-        _ => unimplemented!(),
-    };
+    fn part_2(rules: Self::Input) -> Result<Self::Part2> {
+        part_2(&rules).ok_or(Error::NoSolution)
+    }
+}
 
-    println!("{}", solution);
+impl Generate for Day07 {
+    const INPUT_SIZE: usize = 594;
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-type Bag = (String, String);
-type Rules = HashMap<Bag, HashMap<Bag, usize>>;
+pub type Bag = (String, String);
+pub type Rules = HashMap<Bag, HashMap<Bag, usize>>;
 
-fn parse(input: impl BufRead) -> io::Result<Rules> {
+pub fn parse(input: impl BufRead) -> Result<Rules> {
     let mut rules = HashMap::new();
 
-    for line in input.lines() {
-        let line = line?;
+    for line in lines(input) {
+        let (n, line) = line?;
         let words: Vec<&str> = line.split_whitespace().collect();
 
         match &words[..] {
@@ -34,28 +56,31 @@ fn parse(input: impl BufRead) -> io::Result<Rules> {
                 let mut value = HashMap::new();
 
                 for chunk in rest.chunks(4) {
-                    match chunk {
-                        &[count, adj, colour, _] => {
+                    match *chunk {
+                        [count, adj, colour, _] => {
                             value.insert(
                                 (adj.to_string(), colour.to_string()),
-                                count.parse::<usize>().unwrap(),
+                                count.parse::<usize>().at(n, column(&line, count))?,
                             );
                         }
-                        &["no", "other", "bags."] => (),
-                        _ => unreachable!(),
+                        ["no", "other", "bags."] => (),
+                        _ => {
+                            return Err(Error::parse("Expected a count and a bag")
+                                .at(n, column(&line, chunk[0])))
+                        }
                     }
                 }
 
                 rules.insert(key, value);
             }
-            _ => unreachable!(),
+            _ => return Err(Error::parse("Expected \"<bag> bags contain\"").on_line(n)),
         }
     }
 
     Ok(rules)
 }
 
-fn part_1(rules: &Rules) -> usize {
+pub fn part_1(rules: &Rules) -> usize {
     let shiny = ("shiny".to_string(), "gold".to_string());
 
     // Map from a bag to the bags that could be wrapping it
@@ -63,7 +88,7 @@ fn part_1(rules: &Rules) -> usize {
 
     for (outer, v) in rules {
         for inner in v.keys() {
-            let entry = outers.entry(inner).or_insert(Vec::new());
+            let entry = outers.entry(inner).or_default();
             entry.push(outer);
         }
     }
@@ -71,7 +96,9 @@ fn part_1(rules: &Rules) -> usize {
     let mut outermost = HashSet::new();
     let mut to_visit = Vec::new();
 
-    to_visit.append(outers.get_mut(&shiny).unwrap());
+    if let Some(inners) = outers.get_mut(&shiny) {
+        to_visit.append(inners);
+    }
 
     while let Some(bag) = to_visit.pop() {
         let next = outers.get_mut(&bag);
@@ -86,15 +113,19 @@ fn part_1(rules: &Rules) -> usize {
     outermost.len()
 }
 
-fn part_2(rules: &Rules) -> usize {
+pub fn part_2(rules: &Rules) -> Option<usize> {
     let mut contains = HashMap::new();
     let mut to_visit: VecDeque<&Bag> = rules.keys().collect();
 
+    // Number of bags re-visited in a row, without learning anything new
+    let mut stalled = 0;
+
     while let Some(bag) = to_visit.pop_back() {
         let inside = rules.get(bag).unwrap();
 
         if inside.is_empty() {
             contains.insert(bag, 0);
+            stalled = 0;
         } else {
             let mut known = true;
             let mut count = 0;
@@ -109,21 +140,152 @@ fn part_2(rules: &Rules) -> usize {
 
             if known {
                 contains.insert(bag, count);
+                stalled = 0;
             } else {
                 to_visit.push_front(bag); // Need to re-visit bag
+                stalled += 1;
+
+                // Every bag left contains itself, or a bag without rule
+                if stalled > to_visit.len() {
+                    return None;
+                }
             }
         }
     }
 
     let shiny = ("shiny".to_string(), "gold".to_string());
 
-    *contains.get(&shiny).unwrap()
+    contains.get(&shiny).copied()
+}
+
+const ADJECTIVES: [&str; 18] = [
+    "bright", "clear", "dark", "dim", "dotted", "drab", "dull", "faded", "light", "mirrored",
+    "muted", "pale", "plaid", "posh", "shiny", "striped", "vibrant", "wavy",
+];
+
+const COLOURS: [&str; 33] = [
+    "aqua",
+    "beige",
+    "black",
+    "blue",
+    "bronze",
+    "brown",
+    "chartreuse",
+    "coral",
+    "crimson",
+    "cyan",
+    "fuchsia",
+    "gold",
+    "gray",
+    "green",
+    "indigo",
+    "lavender",
+    "lime",
+    "magenta",
+    "maroon",
+    "olive",
+    "orange",
+    "plum",
+    "purple",
+    "red",
+    "salmon",
+    "silver",
+    "tan",
+    "teal",
+    "tomato",
+    "turquoise",
+    "violet",
+    "white",
+    "yellow",
+];
+
+/// Rules of `size` bags (594 at most) which can't contain themselves, with
+/// shiny gold bags both containing and contained in other bags
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const LEVELS: usize = 7;
+    const SHINY_LEVEL: usize = 3;
+
+    let mut bags: Vec<String> = ADJECTIVES
+        .iter()
+        .flat_map(|adj| {
+            COLOURS
+                .iter()
+                .map(move |colour| format!("{} {}", adj, colour))
+        })
+        .filter(|bag| bag != "shiny gold")
+        .collect();
+    rng.shuffle(&mut bags);
+    bags.truncate(size.clamp(3, bags.len() + 1) - 1);
+    bags.push("shiny gold".to_string());
+
+    // Bags only contain bags of deeper levels, so there are no cycles and the
+    // number of bags inside another stays reasonable
+    let mut levels: Vec<usize> = bags.iter().map(|_| rng.index(LEVELS)).collect();
+    let shiny = bags.len() - 1;
+    levels[shiny] = SHINY_LEVEL;
+    levels[0] = SHINY_LEVEL - 1;
+    levels[1] = SHINY_LEVEL + 1;
+
+    let mut contents: Vec<Vec<(usize, u64)>> = levels
+        .iter()
+        .map(|&level| {
+            let deeper: Vec<usize> = (0..bags.len()).filter(|&i| levels[i] > level).collect();
+
+            if deeper.is_empty() || rng.ratio(1, 5) {
+                return Vec::new();
+            }
+
+            let mut inner: Vec<(usize, u64)> = (0..rng.range(1..5))
+                .map(|_| (*rng.choose(&deeper), rng.range(1..6)))
+                .collect();
+            inner.sort_unstable_by_key(|&(bag, _)| bag);
+            inner.dedup_by_key(|&mut (bag, _)| bag);
+            inner
+        })
+        .collect();
+
+    if contents[shiny].is_empty() {
+        contents[shiny].push((1, rng.range(1..6)));
+    }
+
+    // Some bags of the level above contain shiny gold bags directly
+    for (bag, inner) in contents.iter_mut().enumerate() {
+        let direct = bag == 0 || rng.ratio(1, 3);
+
+        if levels[bag] == SHINY_LEVEL - 1 && direct && inner.iter().all(|&(i, _)| i != shiny) {
+            inner.push((shiny, rng.range(1..6)));
+        }
+    }
+
+    let mut rules: Vec<String> = bags
+        .iter()
+        .zip(&mut contents)
+        .map(|(bag, inner)| {
+            if inner.is_empty() {
+                return format!("{} bags contain no other bags.\n", bag);
+            }
+
+            rng.shuffle(inner);
+            let inner: Vec<String> = inner
+                .iter()
+                .map(|&(i, count)| {
+                    let plural = if count == 1 { "" } else { "s" };
+                    format!("{} {} bag{}", count, bags[i], plural)
+                })
+                .collect();
+
+            format!("{} bags contain {}.\n", bag, inner.join(", "))
+        })
+        .collect();
+
+    rng.shuffle(&mut rules);
+    rules.concat()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test::Bencher;
+    use std::io;
 
     const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
         dark orange bags contain 3 bright white bags, 4 muted yellow bags.
@@ -148,17 +310,27 @@ mod tests {
         let rules = parse(io::Cursor::new(EXAMPLE)).unwrap();
         let solution = part_2(&rules);
 
-        assert_eq!(solution, 32);
+        assert_eq!(solution, Some(32));
     }
 
-    #[bench]
-    fn part_2_bench(b: &mut Bencher) {
-        let input = include_str!("../inputs/day_07.txt");
-        let input = io::Cursor::new(input);
+    #[test]
+    fn part_2_missing_rule() {
+        let rules = parse(io::Cursor::new("shiny gold bags contain 2 dark red bags.")).unwrap();
+
+        assert_eq!(part_2(&rules), None);
+    }
 
-        b.iter(|| {
-            let rules = parse(input.clone()).unwrap();
-            part_2(&rules)
-        });
+    #[test]
+    fn parse_errors() {
+        let error = parse(io::Cursor::new(
+            "shiny gold bags contain two dark red bags.",
+        ))
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 25: invalid digit found in string"
+        );
+
+        assert!(parse(io::Cursor::new("shiny gold bags")).is_err());
     }
 }
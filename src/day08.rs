@@ -1,38 +1,76 @@
-use std::io;
+//! Day 8: Handheld Halting
+
+use std::fmt;
 use std::io::prelude::*;
-use std::num;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let instrs = parse(input).unwrap();
+use serde::{Deserialize, Serialize};
 
-    let solution = match part {
-        1 => part_1(&instrs),
-        2 => part_2(&instrs).unwrap(),
-        _ => unimplemented!(),
-    };
+use crate::error::Context;
+use crate::parallel;
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day08;
 
-    println!("{}", solution);
+impl Solution for Day08 {
+    const DAY: u8 = 8;
+
+    type Input = Instrs;
+    type Part1 = isize;
+    type Part2 = isize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    Ok(())
+    fn part_1(instrs: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&instrs))
+    }
+
+    fn part_2(instrs: Self::Input) -> Result<Self::Part2> {
+        part_2(&instrs).ok_or(Error::NoSolution)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Instr {
+impl Generate for Day08 {
+    const INPUT_SIZE: usize = 600;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Instr {
     Acc(isize),
     Jmp(isize),
     Nop(isize),
 }
 
-type Instrs = Vec<Instr>;
+/// As in the puzzle input, e.g. `jmp -3`
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instr::Acc(n) => write!(f, "acc {:+}", n),
+            Instr::Jmp(n) => write!(f, "jmp {:+}", n),
+            Instr::Nop(n) => write!(f, "nop {:+}", n),
+        }
+    }
+}
+
+pub type Instrs = Vec<Instr>;
 
-struct CPU {
+/// Handheld console, running a program until it terminates or loops
+#[derive(Debug, Clone)]
+pub struct CPU {
     accumulator: isize,
     program_counter: usize,
     program: Vec<(Instr, bool)>,
 }
 
 impl CPU {
-    fn new(instrs: &Instrs) -> Self {
+    pub fn new(instrs: &Instrs) -> Self {
         let program = instrs.iter().map(|&i| (i, false)).collect();
 
         Self {
@@ -42,8 +80,31 @@ impl CPU {
         }
     }
 
-    fn step(&mut self) -> bool {
-        let (instr, visited) = self.program.get_mut(self.program_counter).unwrap();
+    pub fn accumulator(&self) -> isize {
+        self.accumulator
+    }
+
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// Instruction at the program counter, and whether it already ran
+    pub fn next_instr(&self) -> Option<(Instr, bool)> {
+        self.program.get(self.program_counter).copied()
+    }
+
+    /// Whether the program counter went right after the last instruction
+    pub fn terminated(&self) -> bool {
+        self.program_counter == self.program.len()
+    }
+
+    /// Run the next instruction, returns false instead when the program
+    /// terminated or is about to run an instruction a second time
+    pub fn step(&mut self) -> bool {
+        let (instr, visited) = match self.program.get_mut(self.program_counter) {
+            Some(instruction) => instruction,
+            None => return false, // Don't step, the program terminated
+        };
 
         if *visited {
             return false; // Don't step, about to enter a loop
@@ -67,103 +128,141 @@ impl CPU {
     }
 }
 
-fn parse_instr(input: &[u8]) -> Result<Instr> {
-    let sign = match input[4] {
-        b'-' => -1,
-        b'+' => 1,
-        _ => return custom_err("No sign")?,
+pub fn parse_instr(input: &[u8]) -> Result<Instr> {
+    if input.get(3) != Some(&b' ') {
+        return Err(Error::parse("Expected a space").at_column(4));
+    }
+    let sign: isize = match input.get(4) {
+        Some(b'-') => -1,
+        Some(b'+') => 1,
+        _ => return Err(Error::parse("No sign").at_column(5)),
     };
-    let amount: isize = std::str::from_utf8(&input[5..])?.parse()?;
+    let digits = &input[5..];
+    if let Some(b'-' | b'+') = digits.first() {
+        return Err(Error::parse("Expected a digit after the sign").at_column(6));
+    }
+    let amount: isize = std::str::from_utf8(digits)?.parse().at_column(6)?;
 
-    let n = sign * amount;
+    let n = sign
+        .checked_mul(amount)
+        .ok_or_else(|| Error::parse("Amount out of range").at_column(6))?;
 
     Ok(match &input[0..3] {
         b"acc" => Instr::Acc(n),
         b"jmp" => Instr::Jmp(n),
         b"nop" => Instr::Nop(n),
-        _ => custom_err(&format!("Instr not supported: {:?}", &input[0..3]))?,
+        op => {
+            let message = format!("Instr not supported: {}", String::from_utf8_lossy(op));
+            return Err(Error::parse(message).at_column(1));
+        }
     })
 }
 
-fn parse(input: impl BufRead) -> Result<Instrs> {
-    input
-        .lines()
-        .map(|l| try { parse_instr(&l?.as_bytes())? })
-        .collect()
+pub fn parse(input: impl BufRead) -> Result<Instrs> {
+    records(input, |l| parse_instr(l.as_bytes())).collect()
 }
 
-fn part_1(instrs: &Instrs) -> isize {
+pub fn part_1(instrs: &Instrs) -> isize {
     let mut cpu = CPU::new(instrs);
 
     while cpu.step() {}
 
-    cpu.accumulator
+    cpu.accumulator()
 }
 
-fn part_2(instrs: &Instrs) -> Option<isize> {
-    let potential_corruption =
-        instrs
-            .iter()
-            .copied()
-            .enumerate()
-            .filter_map(|(i, instr)| match instr {
-                Instr::Nop(delta) => Some((i, Instr::Jmp(delta))),
-                Instr::Jmp(delta) => Some((i, Instr::Nop(delta))),
-                _ => None,
-            });
-
-    for (i, corrupt) in potential_corruption {
+pub fn part_2(instrs: &Instrs) -> Option<isize> {
+    // Each potential corruption is tried on its own copy of the program
+    parallel::find_map_first(0..instrs.len(), |i| {
+        let corrupt = match instrs[i] {
+            Instr::Nop(delta) => Instr::Jmp(delta),
+            Instr::Jmp(delta) => Instr::Nop(delta),
+            Instr::Acc(_) => return None,
+        };
+
         let mut instrs = instrs.clone();
         instrs[i] = corrupt;
 
         let mut cpu = CPU::new(&instrs);
 
         while cpu.step() {
-            if cpu.program_counter == instrs.len() {
-                return Some(cpu.accumulator);
+            if cpu.terminated() {
+                return Some(cpu.accumulator());
             }
         }
-    }
 
-    None
+        None
+    })
 }
 
-type Result<T> = std::result::Result<T, Day8Error>;
+/// Program of `size` instructions which loops, but terminates when a single
+/// `jmp` is changed into a `nop`
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(8);
 
-#[derive(Debug)]
-enum Day8Error {
-    IoError(io::Error),
-    ParseError(num::ParseIntError),
-    Utf8Error(std::str::Utf8Error),
-    Custom(String),
+    loop {
+        if let Some(instrs) = generate_program(rng, size) {
+            return instrs.iter().map(|instr| format!("{}\n", instr)).collect();
+        }
+    }
 }
 
-fn custom_err<T>(e: &str) -> Result<T> {
-    Err(Day8Error::Custom(e.to_string()))
-}
+/// The terminating program runs forwards, jumping over traps: blocks of `acc`
+/// ending with two jumps back to their start. Changing any of its instructions
+/// but one of its `nop` leads to a trap, and that `nop` is the one corrupted
+/// into a `jmp`
+fn generate_program(rng: &mut Rng, size: usize) -> Option<Instrs> {
+    let mut instrs = Vec::with_capacity(size);
+    let mut path = Vec::new();
+    // Trap instructions but the last jumps, so that even after changing the
+    // first one reached into a `nop` the program still loops
+    let mut traps = Vec::new();
+    let acc = |rng: &mut Rng| Instr::Acc(rng.range(1..50) as isize * *rng.choose(&[-1, 1]));
+
+    while instrs.len() < size {
+        let pos = instrs.len();
+        path.push(pos);
+
+        if pos + 4 <= size && rng.ratio(1, 5) {
+            let accs = rng.range(1..5).min((size - pos - 3) as u64) as isize;
+            instrs.push(Instr::Jmp(accs + 3));
+            instrs.extend((0..accs).map(|_| acc(rng)));
+            instrs.push(Instr::Jmp(-accs));
+            instrs.push(Instr::Jmp(-accs - 1));
+            traps.extend(pos + 1..pos + 2 + accs as usize);
+        } else if rng.ratio(1, 2) {
+            instrs.push(acc(rng));
+        } else {
+            instrs.push(Instr::Nop(0));
+        }
+    }
+
+    let nops: Vec<usize> = path
+        .iter()
+        .copied()
+        .filter(|&pos| matches!(instrs[pos], Instr::Nop(_)))
+        .collect();
 
-impl From<io::Error> for Day8Error {
-    fn from(error: io::Error) -> Self {
-        Day8Error::IoError(error)
+    if nops.is_empty() || traps.is_empty() {
+        return None;
     }
-}
 
-impl From<num::ParseIntError> for Day8Error {
-    fn from(error: num::ParseIntError) -> Self {
-        Day8Error::ParseError(error)
+    for &pos in &nops {
+        let trap = *rng.choose(&traps) as isize;
+        instrs[pos] = Instr::Nop(trap - pos as isize);
     }
-}
 
-impl From<std::str::Utf8Error> for Day8Error {
-    fn from(error: std::str::Utf8Error) -> Self {
-        Day8Error::Utf8Error(error)
+    let corrupted = *rng.choose(&nops);
+    if let Instr::Nop(delta) = instrs[corrupted] {
+        instrs[corrupted] = Instr::Jmp(delta);
     }
+
+    Some(instrs)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test::Bencher;
+    use std::io;
 
     const EXAMPLE: &str = "nop +0
 acc +1
@@ -187,10 +286,27 @@ acc +6";
         assert_eq!(part_2(&instrs).unwrap(), 8);
     }
 
-    #[bench]
-    fn bench_part_2(b: &mut Bencher) {
-        let instrs = parse(aoc2020::input_file(8).unwrap()).unwrap();
-
-        b.iter(|| part_2(&instrs));
+    #[test]
+    fn parse_errors() {
+        let error = parse(io::Cursor::new("nop +0\nacc 1")).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 5: No sign");
+
+        let error = parse(io::Cursor::new("mul +2")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 1: Instr not supported: mul"
+        );
+
+        assert!(parse(io::Cursor::new("acc")).is_err());
+
+        let error = parse(io::Cursor::new("acc --9223372036854775808")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 6: Expected a digit after the sign"
+        );
+        assert!(parse(io::Cursor::new("acc -+1")).is_err());
+
+        let error = parse(io::Cursor::new("acc_+1")).unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 4: Expected a space");
     }
 }
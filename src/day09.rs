@@ -1,28 +1,48 @@
-use std::io;
+//! Day 9: Encoding Error
+
 use std::io::prelude::*;
 
 use std::collections::VecDeque;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let cipher = parse(input);
-    let preamble_size = 25;
+use crate::error::Context;
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub const PREAMBLE_SIZE: usize = 25;
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    const DAY: u8 = 9;
+
+    type Input = Vec<u64>;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(cipher: Self::Input) -> Result<Self::Part1> {
+        part_1(&cipher, PREAMBLE_SIZE).ok_or(Error::NoSolution)
+    }
 
-    let solution = match part {
-        1 => part_1(&cipher, preamble_size),
-        2 => part_2(&cipher, preamble_size),
-        _ => unimplemented!(),
-    };
+    fn part_2(cipher: Self::Input) -> Result<Self::Part2> {
+        part_2(&cipher, PREAMBLE_SIZE).ok_or(Error::NoSolution)
+    }
+}
 
-    println!("{}", solution);
+impl Generate for Day09 {
+    const INPUT_SIZE: usize = 1000;
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-fn parse(input: impl BufRead) -> Vec<u64> {
-    input
-        .lines()
-        .map(|l| l.unwrap().parse::<u64>().unwrap())
-        .collect()
+pub fn parse(input: impl BufRead) -> Result<Vec<u64>> {
+    records(input, |l| l.parse().at_column(1)).collect()
 }
 
 #[derive(Debug)]
@@ -76,20 +96,14 @@ impl CipherCheck {
     }
 }
 
-fn part_1(cipher: &[u64], preamble_size: usize) -> u64 {
+pub fn part_1(cipher: &[u64], preamble_size: usize) -> Option<u64> {
     let mut cipher_check = CipherCheck::new(preamble_size);
 
-    for &x in cipher {
-        if !cipher_check.advance(x) {
-            return x;
-        }
-    }
-
-    panic!("An answer should have been found by now!");
+    cipher.iter().copied().find(|&x| !cipher_check.advance(x))
 }
 
-fn part_2(cipher: &[u64], preamble_size: usize) -> u64 {
-    let goal = part_1(cipher, preamble_size);
+pub fn part_2(cipher: &[u64], preamble_size: usize) -> Option<u64> {
+    let goal = part_1(cipher, preamble_size)?;
 
     let mut window = VecDeque::new();
     let mut sum = 0;
@@ -109,16 +123,71 @@ fn part_2(cipher: &[u64], preamble_size: usize) -> u64 {
         }
     }
 
-    let &min = window.iter().min().unwrap();
-    let &max = window.iter().max().unwrap();
+    if sum != goal || window.len() < 2 {
+        return None;
+    }
+
+    let &min = window.iter().min()?;
+    let &max = window.iter().max()?;
 
-    min + max
+    Some(min + max)
+}
+
+/// `size` numbers, each the sum of two of the 25 numbers before it but one,
+/// which is the sum of a range of earlier numbers instead
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(PREAMBLE_SIZE + 5);
+    let invalid_at = rng.range((size / 2).max(PREAMBLE_SIZE + 2) as u64..size as u64) as usize;
+
+    let mut cipher: Vec<u64> = (0..PREAMBLE_SIZE).map(|_| rng.range(1..100)).collect();
+    rng.shuffle(&mut cipher);
+
+    while cipher.len() < size {
+        let preamble = &cipher[cipher.len() - PREAMBLE_SIZE..];
+
+        let next = if cipher.len() == invalid_at {
+            generate_invalid(rng, &cipher)
+        } else {
+            // Sum of two of the smallest numbers, which keeps them from
+            // overflowing for a while
+            let mut smallest = preamble.to_vec();
+            smallest.sort_unstable();
+            let i = rng.index(4);
+            let j = (i + rng.range(1..4) as usize) % 4;
+            smallest[i] + smallest[j]
+        };
+
+        cipher.push(next);
+    }
+
+    cipher.iter().map(|x| format!("{}\n", x)).collect()
+}
+
+/// Sum of a range of at least 2 numbers, which isn't the sum of two of the
+/// last 25 numbers and can't be mistaken for a range of a single number
+fn generate_invalid(rng: &mut Rng, cipher: &[u64]) -> u64 {
+    let preamble = &cipher[cipher.len() - PREAMBLE_SIZE..];
+
+    loop {
+        let len = rng.range(2..18).min(cipher.len() as u64 - 1) as usize;
+        let start = rng.index(cipher.len() - len);
+        let sum = cipher[start..start + len].iter().sum();
+
+        let is_pair_sum = preamble
+            .iter()
+            .enumerate()
+            .any(|(i, &x)| preamble[i + 1..].iter().any(|&y| x + y == sum));
+
+        if !is_pair_sum && !cipher.contains(&sum) {
+            return sum;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test::Bencher;
+    use std::io;
 
     const EXAMPLE: &str = "35
 20
@@ -143,29 +212,13 @@ mod tests {
 
     #[test]
     fn part_1_example() {
-        let cipher = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_1(&cipher, 5), 127);
+        let cipher = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_1(&cipher, 5), Some(127));
     }
 
     #[test]
     fn part_2_example() {
-        let cipher = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_2(&cipher, 5), 62);
-    }
-
-    #[bench]
-    fn bench_part_1(b: &mut Bencher) {
-        let input = include_str!("../inputs/day_09.txt");
-        let cipher = parse(io::Cursor::new(input));
-
-        b.iter(|| part_1(&cipher, 25));
-    }
-
-    #[bench]
-    fn bench_part_2(b: &mut Bencher) {
-        let input = include_str!("../inputs/day_09.txt");
-        let cipher = parse(io::Cursor::new(input));
-
-        b.iter(|| part_2(&cipher, 25));
+        let cipher = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_2(&cipher, 5), Some(62));
     }
 }
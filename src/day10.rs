@@ -1,31 +1,47 @@
-use std::io;
+//! Day 10: Adapter Array
+
 use std::io::prelude::*;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let adapters = parse(input);
+use crate::error::Context;
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Generate, Result, Solution};
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u8 = 10;
+
+    type Input = Vec<usize>;
+    type Part1 = usize;
+    type Part2 = usize;
 
-    let solution = match part {
-        1 => part_1(adapters),
-        2 => part_2(adapters),
-        _ => unimplemented!(),
-    };
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    println!("{}", solution);
+    fn part_1(adapters: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(adapters))
+    }
 
-    Ok(())
+    fn part_2(adapters: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(adapters))
+    }
 }
 
-fn parse(mut input: impl BufRead) -> Vec<usize> {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+impl Generate for Day10 {
+    const INPUT_SIZE: usize = 100;
 
-    input_str
-        .lines()
-        .map(|l| l.parse::<usize>().unwrap())
-        .collect()
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-fn part_1(mut adapters: Vec<usize>) -> usize {
+pub fn parse(input: impl BufRead) -> Result<Vec<usize>> {
+    records(input, |l| l.parse().at_column(1)).collect()
+}
+
+pub fn part_1(mut adapters: Vec<usize>) -> usize {
     adapters.sort_unstable();
 
     let offset = std::iter::once(&0).chain(adapters.iter());
@@ -42,10 +58,10 @@ fn part_1(mut adapters: Vec<usize>) -> usize {
     c1 * c3
 }
 
-fn part_2(mut adapters: Vec<usize>) -> usize {
+pub fn part_2(mut adapters: Vec<usize>) -> usize {
     adapters.sort_unstable();
 
-    let device = adapters.last().unwrap() + 3;
+    let device = adapters.last().copied().unwrap_or(0) + 3;
 
     let initial_state = vec![(1, 0)];
 
@@ -67,3 +83,36 @@ fn part_2(mut adapters: Vec<usize>) -> usize {
         })[0]
         .0
 }
+
+/// `size` adapters, in runs of up to 5 adapters 1 jolt apart, separated by
+/// gaps of 3 jolts
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // Number of arrangements of a run, by its length
+    const ARRANGEMENTS: [usize; 6] = [1, 1, 1, 2, 4, 7];
+
+    let mut adapters = Vec::with_capacity(size);
+    let mut arrangements: usize = 1;
+    let mut joltage = 0;
+
+    while adapters.len() < size {
+        let mut len = rng.range(1..6) as usize;
+
+        // Keep the answer to part 2 from overflowing, with runs of a single
+        // arrangement once it is big enough
+        if arrangements.saturating_mul(ARRANGEMENTS[len]) > 1 << 60 {
+            len = 2;
+        }
+
+        arrangements *= ARRANGEMENTS[len];
+        joltage += 3;
+
+        for i in 0..len.min(size - adapters.len()) {
+            adapters.push(joltage + i);
+        }
+
+        joltage += len - 1;
+    }
+
+    rng.shuffle(&mut adapters);
+    adapters.iter().map(|a| format!("{}\n", a)).collect()
+}
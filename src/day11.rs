@@ -1,143 +1,225 @@
-use std::io;
+//! Day 11: Seating System
+
 use std::io::prelude::*;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let map = Map::parse(input);
+use serde::{Deserialize, Serialize};
 
-    let solution = match part {
-        1 => part_1(map),
-        2 => part_2(map),
-        _ => unimplemented!(),
-    };
+use crate::grid::{self, Grid, Pos, DIRECTIONS_8};
+use crate::parallel;
+use crate::rng::Rng;
+use crate::{Generate, Result, Solution};
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    const DAY: u8 = 11;
+
+    type Input = Map;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        Map::parse(input)
+    }
 
-    println!("{}", solution);
+    fn part_1(map: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(map))
+    }
 
-    Ok(())
+    fn part_2(map: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(map))
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Cell {
+impl Generate for Day11 {
+    const INPUT_SIZE: usize = 90;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Cell {
     SeatOccupied,
     SeatEmpty,
     Floor,
 }
 
-#[derive(Debug)]
-struct Map(Vec<Vec<Cell>>);
+impl grid::Cell for Cell {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            b'#' => Some(Cell::SeatOccupied),
+            b'L' => Some(Cell::SeatEmpty),
+            b'.' => Some(Cell::Floor),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Cell::SeatOccupied => '#',
+            Cell::SeatEmpty => 'L',
+            Cell::Floor => '.',
+        }
+    }
+}
+
+/// Seat layout of the waiting area
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Map(Grid<Cell>);
 
 impl Map {
-    fn parse(mut input: impl BufRead) -> Self {
-        let mut input_str = String::new();
-        input.read_to_string(&mut input_str).unwrap();
-
-        Self(
-            input_str
-                .lines()
-                .map(|l| {
-                    l.as_bytes()
-                        .iter()
-                        .map(|b| match b {
-                            b'#' => Cell::SeatOccupied,
-                            b'L' => Cell::SeatEmpty,
-                            b'.' => Cell::Floor,
-                            _ => panic!("Unexpected byte '{}'", b),
-                        })
-                        .collect()
-                })
-                .collect(),
-        )
-    }
-
-    fn neighbour_count(&self, x: usize, y: usize, at_distance: bool) -> u8 {
-        let mut count = 0;
-
-        for j in -1..=1 {
-            for i in -1..=1 {
-                let mut dy = j;
-                let mut dx = i;
-
-                if dy == 0 && dx == 0 {
-                    continue;
-                }
+    pub fn parse(input: impl BufRead) -> Result<Self> {
+        Grid::parse(input).map(Self)
+    }
 
-                // Using macro instead of closure to get around borrowing issues
-                macro_rules! get_cell {
-                    () => {
-                        self.0
-                            .get((y as isize + dy) as usize)
-                            .and_then(|v| v.get((x as isize + dx) as usize))
-                    };
-                }
+    fn neighbour_count(&self, pos: Pos, at_distance: bool) -> usize {
+        let occupied = |p: Pos| self.0[p] == Cell::SeatOccupied;
+
+        if at_distance {
+            DIRECTIONS_8
+                .iter()
+                .filter_map(|&dir| self.0.ray(pos, dir).find(|&p| self.0[p] != Cell::Floor))
+                .filter(|&p| occupied(p))
+                .count()
+        } else {
+            self.0.neighbours_8(pos).filter(|&p| occupied(p)).count()
+        }
+    }
 
-                if at_distance {
-                    while let Some(Cell::Floor) = get_cell!() {
-                        dy += j;
-                        dx += i;
-                    }
-                }
+    pub fn cells(&self) -> &Grid<Cell> {
+        &self.0
+    }
 
-                if let Some(Cell::SeatOccupied) = get_cell!() {
-                    count += 1;
-                }
-            }
+    /// Next generation of seats, and whether any of them changed.
+    /// Occupied seats are left once `tolerance` neighbours are occupied, which
+    /// are the first seats seen in each direction when `at_distance`.
+    pub fn step(&self, tolerance: usize, at_distance: bool) -> (Self, bool) {
+        let width = self.0.width();
+        if width == 0 {
+            return (self.clone(), false);
         }
 
-        count
+        // Every cell only depends on the previous generation
+        let cells = parallel::map(0..self.0.cells().len(), |i| {
+            let pos = (i % width, i / width);
+            let count = self.neighbour_count(pos, at_distance);
+
+            match self.0[pos] {
+                Cell::SeatEmpty if count == 0 => Cell::SeatOccupied,
+                Cell::SeatOccupied if count >= tolerance => Cell::SeatEmpty,
+                cell => cell,
+            }
+        });
+
+        let change = cells != self.0.cells();
+        (Map(Grid::new(width, cells)), change)
     }
 
-    fn step(&self, tolerance: u8, at_distance: bool) -> (Self, bool) {
-        let mut change = false;
-        let map = Map(self
-            .0
-            .iter()
-            .enumerate()
-            .map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .map(|(x, &cell)| {
-                        let count = self.neighbour_count(x, y, at_distance);
-
-                        match cell {
-                            Cell::SeatEmpty if count == 0 => {
-                                change = true;
-                                Cell::SeatOccupied
-                            }
-                            Cell::SeatOccupied if count >= tolerance => {
-                                change = true;
-                                Cell::SeatEmpty
-                            }
-                            _ => cell,
-                        }
-                    })
-                    .collect()
-            })
-            .collect());
-        (map, change)
+    /// Number of occupied seats once they stop changing
+    pub fn stable_count(&mut self, tolerance: usize, at_distance: bool) -> usize {
+        self.stable_count_with(tolerance, at_distance, |_| ())
     }
 
-    fn stable_count(&mut self, tolerance: u8, at_distance: bool) -> usize {
+    /// `stable_count`, calling `on_step` with the initial seats and each
+    /// generation that changed
+    pub fn stable_count_with(
+        &mut self,
+        tolerance: usize,
+        at_distance: bool,
+        mut on_step: impl FnMut(&Self),
+    ) -> usize {
+        on_step(self);
+
         while let (new_map, true) = self.step(tolerance, at_distance) {
             *self = new_map;
+            on_step(self);
         }
 
         self.0
+            .cells()
             .iter()
-            .map(|row| row.iter().filter(|c| **c == Cell::SeatOccupied).count())
-            .sum()
+            .filter(|c| **c == Cell::SeatOccupied)
+            .count()
     }
 }
 
-fn part_1(mut map: Map) -> usize {
+pub fn part_1(mut map: Map) -> usize {
     map.stable_count(4, false)
 }
 
-fn part_2(mut map: Map) -> usize {
+pub fn part_2(mut map: Map) -> usize {
     map.stable_count(5, true)
 }
 
+/// Waiting area of `size` rows of 92 cells where the seats stop changing in
+/// both parts. Like in the puzzle, most of the floor is in aisles: rows and
+/// columns 5 to 10 cells apart.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const WIDTH: usize = 92;
+
+    let height = size.max(1);
+
+    let aisles = |rng: &mut Rng, len: usize| {
+        let mut aisles = vec![false; len];
+        let mut i = rng.index(10);
+
+        while i < len {
+            aisles[i] = true;
+            i += rng.range(5..11) as usize;
+        }
+
+        aisles
+    };
+
+    loop {
+        let columns = aisles(rng, WIDTH);
+        let rows = aisles(rng, height);
+
+        let cells = (0..WIDTH * height)
+            .map(|i| {
+                let floor = if columns[i % WIDTH] || rows[i / WIDTH] {
+                    rng.ratio(2, 3)
+                } else {
+                    rng.ratio(1, 12)
+                };
+
+                if floor {
+                    Cell::Floor
+                } else {
+                    Cell::SeatEmpty
+                }
+            })
+            .collect();
+
+        let map = Map(Grid::new(WIDTH, cells));
+
+        if stabilises(&map, 4, false) && stabilises(&map, 5, true) {
+            return map.0.to_string();
+        }
+    }
+}
+
+/// Whether the seats stop changing in a reasonable number of steps, rather
+/// than oscillating forever
+fn stabilises(map: &Map, tolerance: usize, at_distance: bool) -> bool {
+    let mut map = map.clone();
+
+    for _ in 0..1000 {
+        match map.step(tolerance, at_distance) {
+            (_, false) => return true,
+            (next, true) => map = next,
+        }
+    }
+
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "L.LL.LL.LL
 LLLLLLL.LL
@@ -152,13 +234,13 @@ L.LLLLL.LL";
 
     #[test]
     fn part_1_example() {
-        let map = Map::parse(io::Cursor::new(EXAMPLE));
+        let map = Map::parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(map), 37);
     }
 
     #[test]
     fn part_2_example() {
-        let map = Map::parse(io::Cursor::new(EXAMPLE));
+        let map = Map::parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_2(map), 26);
     }
 }
@@ -1,30 +1,51 @@
+//! Day 12: Rain Risk
+
 use std::convert::TryInto;
-use std::io;
 use std::io::prelude::*;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let instrs = parse(input);
+use serde::{Deserialize, Serialize};
+
+use crate::error::Context;
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day12;
 
-    let solution = match part {
-        1 => part_1(&instrs),
-        2 => part_2(&instrs),
-        _ => unimplemented!(),
-    };
+impl Solution for Day12 {
+    const DAY: u8 = 12;
 
-    println!("{}", solution);
+    type Input = Vec<Instr>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    Ok(())
+    fn part_1(instrs: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&instrs))
+    }
+
+    fn part_2(instrs: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&instrs))
+    }
 }
 
-fn parse(mut input: impl BufRead) -> Vec<Instr> {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+impl Generate for Day12 {
+    const INPUT_SIZE: usize = 780;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
 
-    input_str.lines().map(|l| Instr::parse(l)).collect()
+pub fn parse(input: impl BufRead) -> Result<Vec<Instr>> {
+    records(input, Instr::parse).collect()
 }
 
-#[derive(Debug, Clone)]
-enum Dir {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Dir {
     North,
     East,
     South,
@@ -54,28 +75,35 @@ impl Dir {
     }
 }
 
-#[derive(Debug)]
-enum Instr {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Instr {
     Turn(usize),
     Forward(usize),
     Move(Dir, usize),
 }
 
 impl Instr {
-    fn parse(str: &str) -> Self {
-        let (i, n) = str.split_at(1);
-        let n = n.parse::<usize>().unwrap();
-
-        match i.as_bytes()[0] {
-            b'N' => Instr::Move(Dir::North, n),
-            b'E' => Instr::Move(Dir::East, n),
-            b'S' => Instr::Move(Dir::South, n),
-            b'W' => Instr::Move(Dir::West, n),
-            b'R' => Instr::Turn(n),
-            b'L' => Instr::Turn(360 - n),
-            b'F' => Instr::Forward(n),
-            _ => unreachable!(),
+    pub fn parse(str: &str) -> Result<Self> {
+        let i = str
+            .chars()
+            .next()
+            .ok_or_else(|| Error::parse("Empty instruction"))?;
+        let n = str[i.len_utf8()..].parse::<usize>().at_column(2)?;
+
+        if matches!(i, 'R' | 'L') && n % 90 != 0 {
+            return Err(Error::parse(format!("Cannot turn by {} degrees", n)).at_column(2));
         }
+
+        Ok(match i {
+            'N' => Instr::Move(Dir::North, n),
+            'E' => Instr::Move(Dir::East, n),
+            'S' => Instr::Move(Dir::South, n),
+            'W' => Instr::Move(Dir::West, n),
+            'R' => Instr::Turn(n % 360),
+            'L' => Instr::Turn((360 - n % 360) % 360),
+            'F' => Instr::Forward(n),
+            _ => return Err(Error::parse(format!("Unknown action '{}'", i)).at_column(1)),
+        })
     }
 }
 
@@ -168,7 +196,7 @@ impl Position {
     }
 }
 
-fn part_1(instrs: &[Instr]) -> usize {
+pub fn part_1(instrs: &[Instr]) -> usize {
     let mut position = Position::new();
 
     for instr in instrs {
@@ -178,7 +206,7 @@ fn part_1(instrs: &[Instr]) -> usize {
     position.manhattan()
 }
 
-fn part_2(instrs: &[Instr]) -> usize {
+pub fn part_2(instrs: &[Instr]) -> usize {
     let mut position = Position::new();
 
     for instr in instrs {
@@ -188,9 +216,26 @@ fn part_2(instrs: &[Instr]) -> usize {
     position.manhattan()
 }
 
+/// `size` navigation instructions
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let action = *rng.choose(b"NESWLRFF") as char;
+
+            let n = match action {
+                'L' | 'R' => 90 * rng.range(1..4),
+                _ => rng.range(1..100),
+            };
+
+            format!("{}{}\n", action, n)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "F10
 N3
@@ -200,13 +245,13 @@ F11";
 
     #[test]
     fn part_1_example() {
-        let instrs = parse(io::Cursor::new(EXAMPLE));
+        let instrs = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(&instrs), 25);
     }
 
     #[test]
     fn part_2_example() {
-        let instrs = parse(io::Cursor::new(EXAMPLE));
+        let instrs = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_2(&instrs), 286);
     }
 }
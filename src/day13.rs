@@ -1,73 +1,170 @@
-use std::io;
+//! Day 13: Shuttle Search
+
+use std::convert::TryFrom;
 use std::io::prelude::*;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use crate::error::Context;
+use crate::parsing::comma_list;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u8 = 13;
 
-    let solution = match part {
-        1 => part_1(input),
-        2 => part_2(input.1),
-        _ => unimplemented!(),
-    };
+    type Input = (u64, Vec<Option<u64>>);
+    type Part1 = u64;
+    type Part2 = u64;
 
-    println!("{}", solution);
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        part_1(input).ok_or(Error::NoSolution)
+    }
 
-    Ok(())
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        part_2(input.1).ok_or(Error::NoSolution)
+    }
 }
 
-fn parse(mut input: impl BufRead) -> (u64, Vec<Option<u64>>) {
-    let mut input_str = String::new();
-
-    let _ = input.read_line(&mut input_str);
-    input_str.pop(); // Remove newline char
-    let earliest_time = input_str.parse().unwrap();
-
-    input_str.clear();
-    let _ = input.read_line(&mut input_str);
-    input_str.pop(); // Remove newline char
-    let buses = input_str
-        .split(',')
-        .map(|n| {
-            if n == "x" {
-                None
-            } else {
-                Some(n.parse::<u64>().unwrap())
-            }
-        })
-        .collect();
+impl Generate for Day13 {
+    const INPUT_SIZE: usize = 80;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+fn parse_bus(bus: &str) -> Result<Option<u64>> {
+    if bus == "x" {
+        return Ok(None);
+    }
+
+    match bus.parse::<u64>()? {
+        0 => Err(Error::parse("Bus IDs cannot be 0")),
+        bus => Ok(Some(bus)),
+    }
+}
 
-    (earliest_time, buses)
+pub fn parse(input: impl BufRead) -> Result<(u64, Vec<Option<u64>>)> {
+    let mut lines = input.lines();
+
+    let line = lines
+        .next()
+        .ok_or_else(|| Error::parse("Missing earliest timestamp").on_line(1))??;
+    let earliest_time = line.parse().at(1, 1)?;
+
+    let line = lines
+        .next()
+        .ok_or_else(|| Error::parse("Missing bus IDs").on_line(2))??;
+    let buses = comma_list(&line, parse_bus).on_line(2)?;
+
+    Ok((earliest_time, buses))
 }
 
-fn part_1((earliest, buses): (u64, Vec<Option<u64>>)) -> u64 {
+/// `None` without any bus, or when the answer doesn't fit in a `u64`
+pub fn part_1((earliest, buses): (u64, Vec<Option<u64>>)) -> Option<u64> {
     let (b, t) = buses
         .into_iter()
         .filter_map(|b| b.map(|b| (b, b - earliest % b)))
-        .min_by_key(|(_, t)| *t)
-        .unwrap();
+        .min_by_key(|(_, t)| *t)?;
+
+    b.checked_mul(t)
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Inverse of `a` modulo `m`, with `a` and `m` coprime
+fn mod_inverse(a: u64, m: u64) -> u64 {
+    let (mut r, mut next_r) = (a as i128, m as i128);
+    let (mut s, mut next_s) = (1, 0);
 
-    b * t
+    while next_r != 0 {
+        let q = r / next_r;
+        (r, next_r) = (next_r, r - q * next_r);
+        (s, next_s) = (next_s, s - q * next_s);
+    }
+
+    s.rem_euclid(m as i128) as u64
 }
 
-fn part_2(buses: Vec<Option<u64>>) -> u64 {
+/// `None` when no timestamp works for all the buses (with IDs which aren't
+/// coprime), or it doesn't fit in a `u64`
+pub fn part_2(buses: Vec<Option<u64>>) -> Option<u64> {
     buses
         .into_iter()
         .enumerate()
         .filter_map(|(i, b)| b.map(|b| (i as u64, b)))
-        .fold((0, 1), |(mut offset, period), (bus_offset, bus)| {
-            while (offset + bus_offset) % bus != 0 {
-                offset += period;
+        .try_fold((0, 1), |(offset, period): (u64, u64), (bus_offset, bus)| {
+            // The timestamps found so far only reach the multiples of the gcd
+            let gcd = gcd(period, bus);
+            let late = ((offset as u128 + bus_offset as u128) % bus as u128) as u64;
+            if !late.is_multiple_of(gcd) {
+                return None;
             }
 
-            // Period should use LCM, this is a cheap LCM that assumes prime buses
-            (offset, period * bus)
+            // Smallest number of periods to add so that the bus leaves
+            // `bus_offset` after the timestamp
+            let modulus = bus / gcd;
+            let wait = (bus - late) % bus / gcd;
+            let periods = wait as u128 * mod_inverse(period / gcd % modulus, modulus) as u128
+                % modulus as u128;
+            let offset = u64::try_from(offset as u128 + periods * period as u128).ok()?;
+
+            Some((offset, (period / gcd).checked_mul(bus)?))
         })
-        .0
+        .map(|(offset, _)| offset)
+}
+
+/// Earliest timestamp and a schedule of `size` slots, with up to 9 buses of
+/// distinct prime IDs (the first one in the first slot)
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(2);
+
+    let mut primes: Vec<u64> = (13..1000u64)
+        .filter(|&n| (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0))
+        .collect();
+    rng.shuffle(&mut primes);
+
+    // The answer to part 2 is less than the product of the IDs
+    let mut product = 1u64;
+    let mut ids = Vec::new();
+
+    for &prime in &primes {
+        if ids.len() == 9 || ids.len() == size || product * prime > 1 << 53 {
+            break;
+        }
+
+        product *= prime;
+        ids.push(prime);
+    }
+
+    let mut slots: Vec<usize> = (1..size).collect();
+    rng.shuffle(&mut slots);
+    slots.truncate(ids.len() - 1);
+    slots.push(0);
+
+    let mut buses = vec!["x".to_string(); size];
+    for (&slot, id) in slots.iter().zip(ids) {
+        buses[slot] = id.to_string();
+    }
+
+    format!("{}\n{}\n", rng.range(100_000..1_000_000), buses.join(","))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "939
 7,13,x,x,59,x,31,19
@@ -75,13 +172,29 @@ mod tests {
 
     #[test]
     fn part_1_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_1(input), 295);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_1(input), Some(295));
     }
 
     #[test]
     fn part_2_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_2(input.1), 1068781);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_2(input.1), Some(1068781));
+    }
+
+    #[test]
+    fn part_2_shared_factors() {
+        assert_eq!(part_2(vec![Some(4), Some(6)]), None);
+        assert_eq!(part_2(vec![Some(4), None, Some(6)]), Some(4));
+
+        let input = parse(io::Cursor::new("782140\n131,99,163,3795\n")).unwrap();
+        assert_eq!(part_2(input.1), None);
+
+        let input = parse(io::Cursor::new("0\n4294967291,4294967279,4294967231\n")).unwrap();
+        assert_eq!(part_2(input.1), None);
+
+        let input = parse(io::Cursor::new("1\n18446744073709551615\n")).unwrap();
+        assert_eq!(part_1(input.clone()), None);
+        assert_eq!(part_2(input.1), Some(0));
     }
 }
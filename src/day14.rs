@@ -1,50 +1,92 @@
-use std::io;
+//! Day 14: Docking Data
+
 use std::io::prelude::*;
 
 use std::collections::HashMap;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let instrs = parse(input);
+use serde::{Deserialize, Serialize};
+
+use crate::error::{column, Context};
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u8 = 14;
 
-    let solution = match part {
-        1 => part_1(&instrs),
-        2 => part_2(&instrs),
-        _ => unimplemented!(),
-    };
+    type Input = Vec<Instr>;
+    type Part1 = usize;
+    type Part2 = usize;
 
-    println!("{}", solution);
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(instrs: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&instrs))
+    }
 
-    Ok(())
+    fn part_2(instrs: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&instrs))
+    }
 }
 
-fn parse(mut input: impl BufRead) -> Vec<Instr> {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+impl Generate for Day14 {
+    const INPUT_SIZE: usize = 580;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
 
-    input_str.lines().map(|l| Instr::parse(l)).collect()
+pub fn parse(input: impl BufRead) -> Result<Vec<Instr>> {
+    records(input, Instr::parse).collect()
 }
 
-enum Instr {
+/// Number of bits of the masks and addresses
+pub const MASK_SIZE: usize = 36;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Instr {
     Write { addr: usize, value: usize },
     Mask(Vec<u8>),
 }
 
 impl Instr {
-    fn parse(input: &str) -> Self {
-        let (i, payload) = input.split_once(" = ").unwrap();
+    pub fn parse(input: &str) -> Result<Self> {
+        let (i, payload) = input
+            .split_once(" = ")
+            .ok_or_else(|| Error::parse("Expected \" = \""))?;
 
         match i {
-            "mask" => Instr::Mask(payload.as_bytes().to_vec()),
+            "mask" => {
+                if payload.len() != MASK_SIZE {
+                    return Err(
+                        Error::parse(format!("Expected a mask of {} bits", MASK_SIZE))
+                            .at_column(column(input, payload)),
+                    );
+                }
+
+                if let Some(j) = payload
+                    .bytes()
+                    .position(|b| !matches!(b, b'0' | b'1' | b'X'))
+                {
+                    return Err(Error::parse("Expected 0, 1 or X in the mask")
+                        .at_column(column(input, payload) + j));
+                }
+
+                Ok(Instr::Mask(payload.as_bytes().to_vec()))
+            }
             mem => {
                 let addr = mem
                     .strip_prefix("mem[")
-                    .unwrap()
-                    .strip_suffix("]")
-                    .unwrap()
-                    .parse::<usize>()
-                    .unwrap();
-                let value = payload.parse::<usize>().unwrap();
-                Instr::Write { addr, value }
+                    .and_then(|a| a.strip_suffix(']'))
+                    .ok_or_else(|| Error::parse("Expected mask or mem[<address>]").at_column(1))?;
+                let addr = addr.parse::<usize>().at_column(column(input, addr))?;
+                let value = payload.parse::<usize>().at_column(column(input, payload))?;
+                Ok(Instr::Write { addr, value })
             }
         }
     }
@@ -114,7 +156,7 @@ impl State1 {
     }
 }
 
-fn part_1(instrs: &[Instr]) -> usize {
+pub fn part_1(instrs: &[Instr]) -> usize {
     let mut state = State1::new();
     state.run(instrs);
     state.mem.values().sum()
@@ -195,15 +237,42 @@ impl State2 {
         instrs.iter().for_each(|i| self.step(i))
     }
 }
-fn part_2(instrs: &[Instr]) -> usize {
+pub fn part_2(instrs: &[Instr]) -> usize {
     let mut state = State2::new();
     state.run(instrs);
     state.mem.values().sum()
 }
 
+/// `size` instructions, with masks of up to 9 floating bits
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut instrs = Vec::with_capacity(size);
+
+    while instrs.len() < size {
+        let mut mask: Vec<char> = (0..MASK_SIZE)
+            .map(|_| if rng.ratio(1, 2) { '0' } else { '1' })
+            .collect();
+
+        for _ in 0..rng.range(3..10) {
+            mask[rng.index(MASK_SIZE)] = 'X';
+        }
+
+        instrs.push(format!("mask = {}\n", mask.iter().collect::<String>()));
+
+        for _ in 0..rng.range(1..7) {
+            let addr = rng.range(0..1 << 16);
+            let value = rng.range(0..1 << 30);
+            instrs.push(format!("mem[{}] = {}\n", addr, value));
+        }
+    }
+
+    instrs.truncate(size.max(1));
+    instrs.concat()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
 
     #[test]
     fn part_1_example() {
@@ -213,7 +282,7 @@ mem[7] = 101
 mem[8] = 0
 ";
 
-        let instrs = parse(io::Cursor::new(EXAMPLE));
+        let instrs = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(&instrs), 165);
     }
 
@@ -225,7 +294,7 @@ mask = 00000000000000000000000000000000X0XX
 mem[26] = 1
 ";
 
-        let instrs = parse(io::Cursor::new(EXAMPLE));
+        let instrs = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_2(&instrs), 208);
     }
 }
@@ -1,44 +1,66 @@
-use std::io;
+//! Day 15: Rambunctious Recitation
+
 use std::io::prelude::*;
 
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::iter::FromIterator;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use crate::error::Context;
+use crate::parsing::{comma_list, single_line};
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    const DAY: u8 = 15;
+
+    type Input = Vec<usize>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&input))
+    }
 
-    let solution = match part {
-        1 => part_1(&input),
-        2 => part_2(&input),
-        _ => unimplemented!(),
-    };
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&input))
+    }
+}
 
-    println!("{}", solution);
+impl Generate for Day15 {
+    const INPUT_SIZE: usize = 6;
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-fn parse(mut input: impl BufRead) -> Vec<usize> {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+pub fn parse(input: impl BufRead) -> Result<Vec<usize>> {
+    let line = single_line(input)?;
+
+    if line.is_empty() {
+        return Err(Error::parse("Expected starting numbers").on_line(1));
+    }
 
-    input_str.pop(); // Remove newline
-    input_str
-        .split(',')
-        .map(|s| s.parse::<usize>().unwrap())
-        .collect()
+    comma_list(&line, |n| Ok(n.parse()?)).on_line(1)
 }
 
-fn part_1(input: &[usize]) -> usize {
+pub fn part_1(input: &[usize]) -> usize {
     simulate_to::<HashMap<usize, usize>>(2020, input)
 }
 
-fn part_2(input: &[usize]) -> usize {
+pub fn part_2(input: &[usize]) -> usize {
     simulate_to::<VecMap<usize>>(30000000, input)
 }
 
-trait Insertable {
+/// Map from the numbers to the last turn they were spoken
+pub trait Insertable {
     type Key;
     type Item;
     fn insert(&mut self, k: Self::Key, v: Self::Item) -> Option<Self::Item>;
@@ -52,7 +74,8 @@ impl<K: Eq + Hash, T> Insertable for HashMap<K, T> {
     }
 }
 
-fn simulate_to<M>(nth: usize, input: &[usize]) -> usize
+/// Number spoken on the `nth` turn, using `M` to remember the numbers
+pub fn simulate_to<M>(nth: usize, input: &[usize]) -> usize
 where
     M: FromIterator<(usize, usize)> + Insertable<Key = usize, Item = usize>,
 {
@@ -76,7 +99,8 @@ where
     current
 }
 
-struct VecMap<T>(Vec<Option<T>>);
+/// Map growing a vector to fit the keys
+pub struct VecMap<T>(Vec<Option<T>>);
 
 impl<T> Insertable for VecMap<T> {
     type Key = usize;
@@ -104,7 +128,8 @@ impl<T> FromIterator<(usize, T)> for VecMap<T> {
     }
 }
 
-struct SizedMap<T, const N: usize>(Box<[Option<T>]>);
+/// Map with room for the keys up to `N`
+pub struct SizedMap<T, const N: usize>(Box<[Option<T>]>);
 
 impl<T, const N: usize> Insertable for SizedMap<T, N> {
     type Key = usize;
@@ -130,10 +155,19 @@ impl<T: Clone, const N: usize> FromIterator<(usize, T)> for SizedMap<T, N> {
     }
 }
 
+/// `size` distinct starting numbers, below 20
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut numbers: Vec<String> = (0..20).map(|n: usize| n.to_string()).collect();
+    rng.shuffle(&mut numbers);
+    numbers.truncate(size.clamp(1, 20));
+
+    format!("{}\n", numbers.join(","))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use test::Bencher;
+    use proptest::prelude::*;
 
     #[test]
     fn simulate_examples() {
@@ -165,18 +199,16 @@ mod tests {
         assert_eq!(simulate_to::<SizedMap<usize, 2048>>(2020, &[3, 1, 2]), 1836);
     }
 
-    #[bench]
-    fn bench_hash_map(b: &mut Bencher) {
-        b.iter(|| simulate_to::<HashMap<usize, usize>>(10_000, &[0, 3, 6]));
-    }
+    proptest! {
+        #[test]
+        fn maps_agree(
+            (nth, starting) in prop::collection::vec(0..50usize, 1..8)
+                .prop_flat_map(|starting| (starting.len()..3000, Just(starting)))
+        ) {
+            let expected = simulate_to::<HashMap<usize, usize>>(nth, &starting);
 
-    #[bench]
-    fn bench_vec_map(b: &mut Bencher) {
-        b.iter(|| simulate_to::<VecMap<usize>>(10_000, &[0, 3, 6]));
-    }
-
-    #[bench]
-    fn bench_size_map(b: &mut Bencher) {
-        b.iter(|| simulate_to::<SizedMap<usize, 10_000>>(10_000, &[0, 3, 6]));
+            prop_assert_eq!(simulate_to::<VecMap<usize>>(nth, &starting), expected);
+            prop_assert_eq!(simulate_to::<SizedMap<usize, 4096>>(nth, &starting), expected);
+        }
     }
 }
@@ -1,48 +1,89 @@
+//! Day 16: Ticket Translation
+
 use std::io::prelude::*;
-use std::{collections::VecDeque, io};
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::ops::RangeInclusive;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use serde::{Deserialize, Serialize};
 
-    let solution = match part {
-        1 => part_1(input),
-        2 => part_2(input),
-        _ => unimplemented!(),
-    };
+use crate::error::{column, Context};
+use crate::parsing::{blocks, comma_list};
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u8 = 16;
+
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    println!("{}", solution);
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(input))
+    }
 
-    Ok(())
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        part_2(input).ok_or(Error::NoSolution)
+    }
 }
 
-fn parse(mut input: impl BufRead) -> Input {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+impl Generate for Day16 {
+    const INPUT_SIZE: usize = 240;
 
-    input_str.pop(); // Remove final newline
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
 
-    let mut sections = input_str.split("\n\n");
-    let rules = Rules::parse(sections.next().unwrap());
+pub fn parse(input: impl BufRead) -> Result<Input> {
+    let mut blocks = blocks(input);
+    let mut next_block = |missing: &str| {
+        blocks
+            .next()
+            .unwrap_or_else(|| Err(Error::parse(format!("Missing {}", missing))))
+    };
 
-    let mut raw_my_ticket = sections.next().unwrap().split('\n');
-    let my_ticket = Ticket::parse(raw_my_ticket.nth(1).unwrap());
+    let rules = next_block("rules")?
+        .records(Rule::parse_named)
+        .collect::<Result<_>>()?;
 
-    let mut raw_nearby_tickets = sections.next().unwrap().split('\n');
-    raw_nearby_tickets.next(); // drop section name
-    let nearby_tickets = raw_nearby_tickets.map(Ticket::parse).collect();
+    let my_ticket = next_block("your ticket")?;
+    my_ticket.expect_header("your ticket:")?;
+    let my_ticket = match my_ticket.body().lines().collect::<Vec<_>>()[..] {
+        [(n, ticket)] => Ticket::parse(ticket).on_line(n)?,
+        _ => return Err(Error::parse("Expected a single ticket").on_line(my_ticket.first_line)),
+    };
+
+    let nearby_tickets = next_block("nearby tickets")?;
+    nearby_tickets.expect_header("nearby tickets:")?;
+    let nearby_tickets = nearby_tickets
+        .body()
+        .records(Ticket::parse)
+        .collect::<Result<_>>()?;
+
+    if let Some(block) = blocks.next() {
+        return Err(
+            Error::parse("Unexpected lines after the nearby tickets").on_line(block?.first_line)
+        );
+    }
 
-    Input {
-        rules,
+    Ok(Input {
+        rules: Rules(rules),
         my_ticket,
         nearby_tickets,
-    }
+    })
 }
 
-fn part_1(input: Input) -> usize {
+pub fn part_1(input: Input) -> usize {
     input
         .nearby_tickets
         .iter()
@@ -50,7 +91,7 @@ fn part_1(input: Input) -> usize {
         .sum()
 }
 
-fn part_2(input: Input) -> usize {
+pub fn part_2(input: Input) -> Option<usize> {
     let valid_nearby_tickets: Vec<Ticket> = input
         .nearby_tickets
         .iter()
@@ -67,33 +108,41 @@ fn part_2(input: Input) -> usize {
         .map(|(k, v)| (k.clone(), v.potential_indices(&valid_nearby_tickets)))
         .collect();
 
-    while !to_solve.is_empty() {
-        let (k, mut v) = to_solve.pop_back().unwrap();
+    // Number of rules looked at since one was last solved
+    let mut stalled = 0;
 
+    while let Some((k, mut v)) = to_solve.pop_back() {
         v = v.difference(&known_indices).copied().collect();
 
         if v.len() > 1 {
+            // The remaining rules are ambiguous
+            if stalled > to_solve.len() {
+                return None;
+            }
+
             to_solve.push_front((k, v));
+            stalled += 1;
         } else {
-            let x = *v.iter().next().unwrap();
+            let x = *v.iter().next()?;
             known_rules.insert(k, x);
             known_indices.insert(x);
+            stalled = 0;
         }
     }
 
     known_rules
         .into_iter()
         .filter(|(k, _)| k.starts_with("departure"))
-        .map(|(_, i)| input.my_ticket.0[i])
+        .map(|(_, i)| input.my_ticket.0.get(i).copied())
         .product()
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Ticket(Vec<usize>);
 
 impl Ticket {
-    fn parse(s: &str) -> Self {
-        Self(s.split(',').map(|s| s.parse().unwrap()).collect())
+    fn parse(s: &str) -> Result<Self> {
+        comma_list(s, |field| Ok(field.parse()?)).map(Self)
     }
 
     fn potential_indices(&self, rule: &Rule) -> HashSet<usize> {
@@ -122,62 +171,161 @@ impl Ticket {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Rule(RangeInclusive<usize>, RangeInclusive<usize>);
 
 impl Rule {
-    fn parse(s: &str) -> Self {
-        let (a, b) = s.split_once(" or ").unwrap();
-
-        let parse_range = |s: &str| {
-            let (start, end) = s.split_once('-').unwrap();
-            let start = start.parse().unwrap();
-            let end = end.parse().unwrap();
-            RangeInclusive::new(start, end)
+    /// Parse a rule and its name, like `class: 1-3 or 5-7`
+    fn parse_named(line: &str) -> Result<(String, Self)> {
+        let (name, rule) = line
+            .split_once(": ")
+            .ok_or_else(|| Error::parse("Expected \": \" after the rule name"))?;
+        let (a, b) = rule
+            .split_once(" or ")
+            .ok_or_else(|| Error::parse("Expected two ranges").at_column(column(line, rule)))?;
+
+        let parse_range = |s: &str| -> Result<RangeInclusive<usize>> {
+            let (start, end) = s.split_once('-').ok_or_else(|| {
+                Error::parse("Expected a range like 1-3").at_column(column(line, s))
+            })?;
+            let start = start.parse().at_column(column(line, start))?;
+            let end = end.parse().at_column(column(line, end))?;
+            if start > end {
+                return Err(
+                    Error::parse("The range ends before it starts").at_column(column(line, s))
+                );
+            }
+            Ok(RangeInclusive::new(start, end))
         };
 
-        Self(parse_range(a), parse_range(b))
+        Ok((name.to_string(), Self(parse_range(a)?, parse_range(b)?)))
     }
 
     fn potential_indices(&self, tickets: &[Ticket]) -> HashSet<usize> {
         tickets
             .iter()
             .map(|ticket| ticket.potential_indices(self))
-            .fold_first(|a, b| a.intersection(&b).copied().collect())
-            .unwrap()
+            .reduce(|a, b| a.intersection(&b).copied().collect())
+            .unwrap_or_default()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Rules(HashMap<String, Rule>);
 
-impl Rules {
-    fn parse(s: &str) -> Self {
-        Self(
-            s.lines()
-                .map(|s| {
-                    let (k, v) = s.split_once(": ").unwrap();
-                    (k.to_string(), Rule::parse(v))
-                })
-                .collect(),
-        )
-    }
-}
-
-#[derive(Debug)]
-struct Input {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Input {
     rules: Rules,
     my_ticket: Ticket,
     nearby_tickets: Vec<Ticket>,
 }
 
+const FIELDS: [&str; 20] = [
+    "departure location",
+    "departure station",
+    "departure platform",
+    "departure track",
+    "departure date",
+    "departure time",
+    "arrival location",
+    "arrival station",
+    "arrival platform",
+    "arrival track",
+    "class",
+    "duration",
+    "price",
+    "route",
+    "row",
+    "seat",
+    "train",
+    "type",
+    "wagon",
+    "zone",
+];
+
+/// Rules of 20 fields, my ticket and `size` nearby tickets, a fifth of them
+/// with an invalid value. There is a single assignment of the fields.
+///
+/// The values of the field of rank `k` (from 1) are in `40k..40k + 30`, and
+/// its rule accepts the values of the fields of ranks up to `k` only. So the
+/// field of rank 1 only fits its own column, the field of rank 2 fits that
+/// column and its own, and so on.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let count = FIELDS.len();
+
+    let mut ranks: Vec<usize> = (1..=count).collect();
+    rng.shuffle(&mut ranks);
+
+    let mut columns: Vec<usize> = (0..count).collect();
+    rng.shuffle(&mut columns);
+
+    let rules: Vec<String> = FIELDS
+        .iter()
+        .zip(&ranks)
+        .map(|(name, &rank)| {
+            let rank = rank as u64;
+            let start = rng.range(21..41);
+            let end = 40 * rank + 29;
+
+            // Split the range between two bands, or within the only one
+            let (split, next) = if rank == 1 {
+                let split = rng.range(45..65);
+                (split, split + 1)
+            } else {
+                let split = 40 * rng.range(1..rank) + 29 + rng.range(0..5);
+                (split, split + 1 + rng.range(0..6))
+            };
+
+            format!("{}: {}-{} or {}-{}\n", name, start, split, next, end)
+        })
+        .collect();
+
+    let ticket = |rng: &mut Rng| {
+        let mut values = vec![0; count];
+
+        for (&rank, &column) in ranks.iter().zip(&columns) {
+            values[column] = 40 * rank as u64 + rng.range(0..30);
+        }
+
+        values
+    };
+
+    let format_ticket = |values: Vec<u64>| {
+        let values: Vec<String> = values.iter().map(u64::to_string).collect();
+        values.join(",") + "\n"
+    };
+
+    let my_ticket = format_ticket(ticket(rng));
+    let nearby_tickets: String = (0..size.max(1))
+        .map(|i| {
+            let mut values = ticket(rng);
+
+            if i > 0 && rng.ratio(1, 5) {
+                values[rng.index(count)] = if rng.ratio(1, 2) {
+                    rng.range(1..21)
+                } else {
+                    rng.range(900..1000)
+                };
+            }
+
+            format_ticket(values)
+        })
+        .collect();
+
+    format!(
+        "{}\nyour ticket:\n{}\nnearby tickets:\n{}",
+        rules.concat(),
+        my_ticket,
+        nearby_tickets
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
-    #[test]
-    fn part_1_example() {
-        const EXAMPLE: &str = "class: 1-3 or 5-7
+    const EXAMPLE: &str = "class: 1-3 or 5-7
 row: 6-11 or 33-44
 seat: 13-40 or 45-50
 
@@ -191,7 +339,26 @@ nearby tickets:
 38,6,12
 ";
 
-        let input = parse(io::Cursor::new(EXAMPLE));
+    #[test]
+    fn part_1_example() {
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(input), 71);
     }
+
+    #[test]
+    fn crlf_without_trailing_newline() {
+        let example = EXAMPLE.trim_end().replace('\n', "\r\n");
+        let input = parse(io::Cursor::new(example)).unwrap();
+        assert_eq!(part_1(input), 71);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let example = EXAMPLE.replace("5-7", "7-5");
+        let error = parse(io::Cursor::new(example)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 15: The range ends before it starts"
+        );
+    }
 }
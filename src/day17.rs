@@ -1,20 +1,52 @@
-use std::io;
+//! Day 17: Conway Cubes
+
+use std::convert::TryInto;
 use std::io::prelude::*;
 use std::ops::Add;
 
 use std::collections::{HashMap, HashSet};
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let solution = match part {
-        1 => part_1(Grid::parse(input)),
-        2 => part_2(Grid::parse(input)),
-        3 => part_3(Grid::parse(input)),
-        _ => unimplemented!(),
-    };
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::grid;
+use crate::parallel;
+use crate::rng::Rng;
+use crate::solution::BonusPart;
+use crate::{Generate, Result, Solution};
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    const DAY: u8 = 17;
+
+    type Input = Grid<2>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        Grid::parse(input)
+    }
+
+    fn part_1(grid: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(grid))
+    }
+
+    fn part_2(grid: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(grid))
+    }
+
+    fn bonus_parts() -> &'static [BonusPart<Self::Input, Self::Part2>] {
+        &[|grid| Ok(part_3(grid))]
+    }
+}
 
-    println!("{}", solution);
+impl Generate for Day17 {
+    const INPUT_SIZE: usize = 8;
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -79,52 +111,73 @@ impl<const N: usize> Iterator for PosRange<N> {
     }
 }
 
-#[derive(Debug)]
-struct Grid<const N: usize> {
+/// Pocket dimension of `N` dimensions (`N` >= 2)
+#[derive(Debug, Clone)]
+pub struct Grid<const N: usize> {
     grid: HashSet<Pos<N>>, // Only the active cells
 }
 
 impl<const N: usize> Grid<N> {
-    fn parse(mut input: impl BufRead) -> Self {
-        let mut input_str = String::new();
-        input.read_to_string(&mut input_str).unwrap();
-
-        let grid = input_str
-            .lines()
-            .enumerate()
-            .flat_map(|(y, l)| {
-                l.as_bytes()
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, cell)| **cell == b'#')
-                    .map(move |(x, _)| {
-                        let mut pos = [0; N];
-                        pos[0] = x as isize;
-                        pos[1] = y as isize;
-                        Pos(pos)
-                    })
+    pub fn parse(input: impl BufRead) -> Result<Self> {
+        let grid = grid::Grid::<bool>::parse(input)?
+            .iter()
+            .filter(|(_, &active)| active)
+            .map(|((x, y), _)| {
+                let mut pos = [0; N];
+                pos[0] = x as isize;
+                pos[1] = y as isize;
+                Pos(pos)
             })
             .collect();
 
-        Grid { grid }
+        Ok(Grid { grid })
     }
 
-    fn step(&self) -> Self {
-        let mut neighbours = HashMap::<Pos<N>, (bool, usize)>::new();
+    /// Embed the grid in a space with more dimensions
+    pub fn extend<const M: usize>(&self) -> Grid<M> {
+        let grid = self
+            .grid
+            .iter()
+            .map(|pos| {
+                let mut extended = [0; M];
+                extended[..N].copy_from_slice(&pos.0);
+                Pos(extended)
+            })
+            .collect();
 
-        for pos in self.grid.iter() {
-            for dpos in PosRange::new(&Pos::repeated(-1), &Pos::repeated(1)) {
-                let is_current = dpos == Pos::repeated(0);
+        Grid { grid }
+    }
 
-                let entry = neighbours.entry(dpos + pos).or_insert((false, 0));
+    pub fn step(&self) -> Self {
+        // Counts of the batches of active cubes are merged together
+        let neighbours = parallel::fold(
+            &self.grid,
+            HashMap::<Pos<N>, (bool, usize)>::new,
+            |mut neighbours, pos| {
+                for dpos in PosRange::new(&Pos::repeated(-1), &Pos::repeated(1)) {
+                    let is_current = dpos == Pos::repeated(0);
+
+                    let entry = neighbours.entry(dpos + pos).or_insert((false, 0));
+
+                    if !is_current {
+                        entry.1 += 1;
+                    } else {
+                        entry.0 = true; // is active
+                    }
+                }
 
-                if !is_current {
-                    (*entry).1 += 1;
-                } else {
-                    (*entry).0 = true; // is active
+                neighbours
+            },
+            |mut neighbours, other| {
+                for (pos, (active, count)) in other {
+                    let entry = neighbours.entry(pos).or_insert((false, 0));
+                    entry.0 |= active;
+                    entry.1 += count;
                 }
-            }
-        }
+
+                neighbours
+            },
+        );
 
         let grid = neighbours
             .into_iter()
@@ -135,31 +188,87 @@ impl<const N: usize> Grid<N> {
         Grid { grid }
     }
 
-    fn run_steps(&mut self, steps: usize) {
+    pub fn run_steps(&mut self, steps: usize) {
+        self.run_steps_with(steps, |_| ())
+    }
+
+    /// `run_steps`, calling `on_step` with the initial grid and each generation
+    pub fn run_steps_with(&mut self, steps: usize, mut on_step: impl FnMut(&Self)) {
+        on_step(self);
+
         for _ in 0..steps {
             *self = self.step();
+            on_step(self);
         }
     }
+
+    /// `(x, y)` positions of the active cubes on the plane through the origin
+    /// of the other dimensions
+    pub fn plane(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        self.grid
+            .iter()
+            .filter(|pos| pos.0[2..].iter().all(|&c| c == 0))
+            .map(|pos| (pos.0[0], pos.0[1]))
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.grid.len()
+    }
 }
 
-fn part_1(mut grid: Grid<3>) -> usize {
+/// Saved as the list of the coordinates of the active cubes
+impl<const N: usize> Serialize for Grid<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.grid.iter().map(|pos| &pos.0[..]))
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Grid<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let grid = Vec::<Vec<isize>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|pos| {
+                let pos = pos.try_into().map_err(|_| {
+                    D::Error::custom(format!("Expected cubes of {} coordinates", N))
+                })?;
+                Ok(Pos(pos))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Grid { grid })
+    }
+}
+
+pub fn part_1(grid: Grid<2>) -> usize {
+    let mut grid = grid.extend::<3>();
     grid.run_steps(6);
-    grid.grid.len()
+    grid.active_count()
 }
 
-fn part_2(mut grid: Grid<4>) -> usize {
+pub fn part_2(grid: Grid<2>) -> usize {
+    let mut grid = grid.extend::<4>();
     grid.run_steps(6);
-    grid.grid.len()
+    grid.active_count()
 }
 
-fn part_3(mut grid: Grid<5>) -> usize {
+pub fn part_3(grid: Grid<2>) -> usize {
+    let mut grid = grid.extend::<5>();
     grid.run_steps(6);
-    grid.grid.len()
+    grid.active_count()
+}
+
+/// Initial slice of `size` by `size` cubes, half of them active
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let cubes = (0..size * size).map(|_| rng.ratio(1, 2)).collect();
+
+    grid::Grid::new(size, cubes).to_string()
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = ".#.
 ..#
@@ -168,19 +277,19 @@ mod test {
 
     #[test]
     fn part_1_example() {
-        let input = Grid::parse(io::Cursor::new(EXAMPLE));
+        let input = Grid::parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(input), 112);
     }
 
     #[test]
     fn part_2_example() {
-        let input = Grid::parse(io::Cursor::new(EXAMPLE));
+        let input = Grid::parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_2(input), 848);
     }
 
     #[test]
     fn part_3_example() {
-        let input = Grid::parse(io::Cursor::new(EXAMPLE));
+        let input = Grid::parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_3(input), 5760);
     }
 }
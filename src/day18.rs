@@ -1,113 +1,162 @@
-use std::io;
+//! Day 18: Operation Order
+
 use std::io::prelude::*;
 use std::str::FromStr;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use serde::{Deserialize, Serialize};
+
+use crate::error::{column, Context};
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: u8 = 18;
 
-    let solution = match part {
-        1 => part_1(input),
-        2 => part_2(input),
-        _ => unimplemented!(),
-    };
+    type Input = Vec<Expr>;
+    type Part1 = usize;
+    type Part2 = usize;
 
-    println!("{}", solution);
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        part_1(input).ok_or(Error::NoSolution)
+    }
 
-    Ok(())
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        part_2(input).ok_or(Error::NoSolution)
+    }
+}
+
+impl Generate for Day18 {
+    const INPUT_SIZE: usize = 380;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum Op {
     Add,
     Mul,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum LExpr {
     Lit(usize),
     Op(Op),
     Nested(Box<Expr>),
 }
 
-#[derive(Debug)]
-struct Expr {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Expr {
     row: Vec<LExpr>,
 }
 
-fn matching_parens(s: &str) -> usize {
+fn matching_parens(s: &str) -> Option<usize> {
     let mut count = 0;
 
     for (i, byte) in s.as_bytes().iter().enumerate() {
         match byte {
             b'(' => count += 1,
-            b')' if count == 0 => return i,
+            b')' if count == 0 => return Some(i),
             b')' => count -= 1,
             _ => (),
         }
     }
 
-    panic!("No matching parens")
+    None
 }
 
-impl FromStr for Expr {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut s = s;
-
+impl Expr {
+    /// Parse an expression starting `offset` bytes into its line, to locate errors
+    fn parse_at(s: &str, offset: usize) -> Result<Self> {
         let mut row = Vec::new();
+        let mut rest = s.trim_start();
 
-        while !s.is_empty() {
-            match s.strip_prefix('(') {
+        while !rest.is_empty() {
+            let col = offset + column(s, rest);
+
+            let lexpr = match rest.strip_prefix('(') {
                 Some(parens) => {
-                    let i = matching_parens(parens);
-                    let (nested, rest) = parens.split_at(i);
-                    let nested = nested.parse::<Expr>()?;
-                    row.push(LExpr::Nested(Box::new(nested)));
-                    s = rest[1..].trim_start();
+                    let i = matching_parens(parens)
+                        .ok_or_else(|| Error::parse("Unmatched parenthesis").at_column(col))?;
+                    let nested = Expr::parse_at(&parens[..i], col)?;
+                    rest = &parens[i + 1..];
+                    LExpr::Nested(Box::new(nested))
                 }
                 None => {
-                    let word = match s.split_once(' ') {
-                        Some((word, rest)) => {
-                            s = rest;
-                            word
+                    let end = rest.find([' ', '(', ')']).unwrap_or(rest.len());
+                    let (word, after) = rest.split_at(end);
+                    rest = after;
+
+                    match word {
+                        "" => return Err(Error::parse("Unmatched parenthesis").at_column(col)),
+                        "+" => LExpr::Op(Op::Add),
+                        "*" => LExpr::Op(Op::Mul),
+                        _ if word.starts_with(|c: char| c.is_ascii_digit()) => {
+                            LExpr::Lit(word.parse().at_column(col)?)
                         }
-                        None => {
-                            let word = s;
-                            s = "";
-                            word
+                        _ => {
+                            return Err(
+                                Error::parse(format!("Unexpected word: {}", word)).at_column(col)
+                            )
                         }
-                    };
-
-                    if matches!(word.as_bytes()[0], b'0'..=b'9') {
-                        row.push(LExpr::Lit(word.parse().unwrap()));
-                    } else if word == "+" {
-                        row.push(LExpr::Op(Op::Add));
-                    } else if word == "*" {
-                        row.push(LExpr::Op(Op::Mul));
-                    } else {
-                        panic!("Unexpected word: {}", word);
                     }
                 }
+            };
+
+            // Operands and operators alternate, starting with an operand
+            match (row.len() % 2 == 0, &lexpr) {
+                (true, LExpr::Op(_)) => {
+                    return Err(Error::parse("Expected an operand").at_column(col))
+                }
+                (false, LExpr::Lit(_)) | (false, LExpr::Nested(_)) => {
+                    return Err(Error::parse("Expected an operator").at_column(col))
+                }
+                _ => row.push(lexpr),
             }
+
+            rest = rest.trim_start();
+        }
+
+        if row.is_empty() {
+            return Err(Error::parse("Empty expression").at_column(offset + 1));
+        }
+
+        if row.len() % 2 == 0 {
+            return Err(Error::parse("Expected an operand").at_column(offset + s.len() + 1));
         }
 
         Ok(Expr { row })
     }
 }
 
+impl FromStr for Expr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expr::parse_at(s, 0)
+    }
+}
+
 impl LExpr {
-    fn eval(self) -> usize {
+    fn eval(self) -> Option<usize> {
         match self {
-            LExpr::Lit(x) => x,
+            LExpr::Lit(x) => Some(x),
             LExpr::Op(_) => unreachable!(),
             LExpr::Nested(e) => e.eval(),
         }
     }
 
-    fn eval_add_precedence(self) -> usize {
+    fn eval_add_precedence(self) -> Option<usize> {
         match self {
-            LExpr::Lit(x) => x,
+            LExpr::Lit(x) => Some(x),
             LExpr::Op(_) => unreachable!(),
             LExpr::Nested(e) => e.eval_add_precedence(),
         }
@@ -115,72 +164,108 @@ impl LExpr {
 }
 
 impl Expr {
-    fn eval(self) -> usize {
+    /// Evaluate from left to right, without operator precedence, `None` when
+    /// it overflows
+    pub fn eval(self) -> Option<usize> {
         let mut row: Vec<LExpr> = self.row.into_iter().rev().collect();
-        let mut res = 0;
+        let mut res: usize = 0;
 
         while !row.is_empty() {
             match row.pop().unwrap() {
                 LExpr::Op(Op::Add) => {
                     let next = row.pop().unwrap();
-                    res += next.eval();
+                    res = res.checked_add(next.eval()?)?;
                 }
                 LExpr::Op(Op::Mul) => {
                     let next = row.pop().unwrap();
-                    res *= next.eval();
+                    res = res.checked_mul(next.eval()?)?;
                 }
                 other => {
-                    res = other.eval();
+                    res = other.eval()?;
                 }
             }
         }
 
-        res
+        Some(res)
     }
 
-    fn eval_add_precedence(self) -> usize {
+    /// Evaluate with additions before multiplications, `None` when it
+    /// overflows
+    pub fn eval_add_precedence(self) -> Option<usize> {
         let mut row = self.row;
-        let mut res = 0;
+        let mut res: usize = 0;
         let mut stack = Vec::new();
 
         while !row.is_empty() {
             match row.pop().unwrap() {
                 LExpr::Op(Op::Add) => {
                     let next = row.pop().unwrap();
-                    res += next.eval_add_precedence();
+                    res = res.checked_add(next.eval_add_precedence()?)?;
                 }
                 LExpr::Op(Op::Mul) => {
                     stack.push(res);
                 }
                 other => {
-                    res = other.eval_add_precedence();
+                    res = other.eval_add_precedence()?;
                 }
             }
         }
 
         stack.push(res);
 
-        stack.iter().product()
+        stack
+            .into_iter()
+            .try_fold(1, |product: usize, x| product.checked_mul(x))
     }
 }
 
-fn parse(mut input: impl BufRead) -> Vec<Expr> {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+pub fn parse(input: impl BufRead) -> Result<Vec<Expr>> {
+    records(input, str::parse).collect()
+}
 
-    input_str.pop(); // Remove last newline
-    input_str
-        .lines()
-        .map(|s| s.parse::<Expr>().unwrap())
-        .collect()
+/// Sum of the values of the expressions, `None` when it overflows
+pub fn part_1(input: Vec<Expr>) -> Option<usize> {
+    input
+        .into_iter()
+        .try_fold(0, |sum: usize, e| sum.checked_add(e.eval()?))
 }
 
-fn part_1(input: Vec<Expr>) -> usize {
-    input.into_iter().map(|e| e.eval()).sum()
+/// Sum of the values of the expressions, `None` when it overflows
+pub fn part_2(input: Vec<Expr>) -> Option<usize> {
+    input
+        .into_iter()
+        .try_fold(0, |sum: usize, e| sum.checked_add(e.eval_add_precedence()?))
 }
 
-fn part_2(input: Vec<Expr>) -> usize {
-    input.into_iter().map(|e| e.eval_add_precedence()).sum()
+/// `size` expressions of up to 12 numbers, nested up to twice
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| generate_expr(rng, 0, &mut 12) + "\n")
+        .collect()
+}
+
+/// Expression of at least 2 numbers, taken from the `budget` of numbers (which
+/// keeps the values of the expressions in check)
+fn generate_expr(rng: &mut Rng, depth: usize, budget: &mut u64) -> String {
+    let mut expr = String::new();
+    let operands = rng.range(2..7).min(*budget).max(2);
+    *budget = budget.saturating_sub(operands);
+
+    for i in 0..operands {
+        if i > 0 {
+            expr.push_str(if rng.ratio(1, 2) { " + " } else { " * " });
+        }
+
+        if depth < 2 && *budget >= 2 && rng.ratio(1, 4) {
+            expr.push('(');
+            expr.push_str(&generate_expr(rng, depth + 1, budget));
+            expr.push(')');
+        } else {
+            expr.push_str(&rng.range(1..10).to_string());
+        }
+    }
+
+    expr
 }
 
 #[cfg(test)]
@@ -189,35 +274,38 @@ mod test {
 
     #[test]
     fn part_1_example() {
-        assert_eq!("1 + 2 * 3 + 4 * 5 + 6".parse::<Expr>().unwrap().eval(), 71);
+        assert_eq!(
+            "1 + 2 * 3 + 4 * 5 + 6".parse::<Expr>().unwrap().eval(),
+            Some(71)
+        );
         assert_eq!(
             "1 + (2 * 3) + (4 * (5 + 6))"
                 .parse::<Expr>()
                 .unwrap()
                 .eval(),
-            51
+            Some(51)
         );
-        assert_eq!("2 * 3 + (4 * 5)".parse::<Expr>().unwrap().eval(), 26);
+        assert_eq!("2 * 3 + (4 * 5)".parse::<Expr>().unwrap().eval(), Some(26));
         assert_eq!(
             "5 + (8 * 3 + 9 + 3 * 4 * 3)"
                 .parse::<Expr>()
                 .unwrap()
                 .eval(),
-            437
+            Some(437)
         );
         assert_eq!(
             "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"
                 .parse::<Expr>()
                 .unwrap()
                 .eval(),
-            12240
+            Some(12240)
         );
         assert_eq!(
             "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"
                 .parse::<Expr>()
                 .unwrap()
                 .eval(),
-            13632
+            Some(13632)
         );
     }
 
@@ -228,42 +316,66 @@ mod test {
                 .parse::<Expr>()
                 .unwrap()
                 .eval_add_precedence(),
-            231
+            Some(231)
         );
         assert_eq!(
             "1 + (2 * 3) + (4 * (5 + 6))"
                 .parse::<Expr>()
                 .unwrap()
                 .eval_add_precedence(),
-            51
+            Some(51)
         );
         assert_eq!(
             "2 * 3 + (4 * 5)"
                 .parse::<Expr>()
                 .unwrap()
                 .eval_add_precedence(),
-            46
+            Some(46)
         );
         assert_eq!(
             "5 + (8 * 3 + 9 + 3 * 4 * 3)"
                 .parse::<Expr>()
                 .unwrap()
                 .eval_add_precedence(),
-            1445
+            Some(1445)
         );
         assert_eq!(
             "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"
                 .parse::<Expr>()
                 .unwrap()
                 .eval_add_precedence(),
-            669060
+            Some(669060)
         );
         assert_eq!(
             "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"
                 .parse::<Expr>()
                 .unwrap()
                 .eval_add_precedence(),
-            23340
+            Some(23340)
         );
     }
+
+    #[test]
+    fn parse_errors() {
+        let error = |s: &str| s.parse::<Expr>().unwrap_err().to_string();
+
+        assert_eq!(error("1 + (2 * 3"), "column 5: Unmatched parenthesis");
+        assert_eq!(error("1 + 2)"), "column 6: Unmatched parenthesis");
+        assert_eq!(error("1 + (2 3)"), "column 8: Expected an operator");
+        assert_eq!(error("1 + * 2"), "column 5: Expected an operand");
+        assert_eq!(error("1 + (2 +)"), "column 9: Expected an operand");
+        assert_eq!(error("1 + ()"), "column 6: Empty expression");
+        assert_eq!(error("1 - 2"), "column 3: Unexpected word: -");
+    }
+
+    #[test]
+    fn overflow() {
+        let expr: Expr = "999999999999 * 999999999999 * 99999999".parse().unwrap();
+        assert_eq!(expr.clone().eval(), None);
+        assert_eq!(expr.eval_add_precedence(), None);
+
+        let input = parse(format!("{0}\n{0}\n", usize::MAX / 2 + 1).as_bytes()).unwrap();
+        assert_eq!(part_1(input.clone()), None);
+        assert_eq!(part_2(input), None);
+    }
 }
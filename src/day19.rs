@@ -1,50 +1,96 @@
-use std::io;
+//! Day 19: Monster Messages
+
 use std::io::prelude::*;
-use std::num::ParseIntError;
-use std::str::FromStr;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use serde::{Deserialize, Serialize};
 
-    let solution = match part {
-        1 => part_1(input),
-        2 => part_2(input),
-        _ => unimplemented!(),
-    };
+use crate::error::{column, Context};
+use crate::parsing::blocks;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: u8 = 19;
+
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        part_1(input).ok_or(Error::NoSolution)
+    }
 
-    println!("{}", solution);
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        part_2(input).ok_or(Error::NoSolution)
+    }
+}
+
+impl Generate for Day19 {
+    const INPUT_SIZE: usize = 450;
 
-    Ok(())
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum Rule {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Rule {
     Lit(u8), // character
     Series(Vec<usize>),
     Or(Vec<usize>, Vec<usize>),
 }
 
-impl FromStr for Rule {
-    type Err = ParseIntError;
+impl Rule {
+    /// Indices of the rules this one refers to
+    fn references(&self) -> impl Iterator<Item = &usize> {
+        let (a, b): (&[usize], &[usize]) = match self {
+            Rule::Lit(_) => (&[], &[]),
+            Rule::Series(a) => (a, &[]),
+            Rule::Or(a, b) => (a, b),
+        };
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        fn parse_series(s: &str) -> Result<Vec<usize>, ParseIntError> {
-            Ok(s.split(' ')
-                .map(|n| n.parse())
-                .collect::<Result<Vec<usize>, ParseIntError>>()?)
-        }
+        a.iter().chain(b)
+    }
+}
+
+/// Parse a rule and its index, like `1: 2 3 | 3 2`
+fn parse_rule(line: &str) -> Result<(usize, Rule)> {
+    let (idx, rule) = line
+        .split_once(": ")
+        .ok_or_else(|| Error::parse("Expected \": \" after the rule index"))?;
+    let idx = idx.parse().at_column(1)?;
+
+    let parse_series = |series: &str| -> Result<Vec<usize>> {
+        series
+            .split(' ')
+            .map(|n| n.parse().at_column(column(line, n)))
+            .collect()
+    };
 
-        Ok(if s.starts_with('"') {
-            Rule::Lit(s.trim_matches('"').as_bytes()[0])
-        } else {
-            match s.split_once(" | ") {
-                Some((a, b)) => Rule::Or(parse_series(a)?, parse_series(b)?),
-                None => Rule::Series(parse_series(s)?),
+    let rule = if let Some(lit) = rule.strip_prefix('"') {
+        match lit.strip_suffix('"').map(str::as_bytes) {
+            Some(&[char]) => Rule::Lit(char),
+            _ => {
+                return Err(Error::parse("Expected a single quoted character")
+                    .at_column(column(line, rule)))
             }
-        })
-    }
+        }
+    } else {
+        match rule.split_once(" | ") {
+            Some((a, b)) => Rule::Or(parse_series(a)?, parse_series(b)?),
+            None => Rule::Series(parse_series(rule)?),
+        }
+    };
+
+    Ok((idx, rule))
 }
 
 fn match_message<'msg>(idx: &usize, rules: &Rules, msg: &'msg [u8]) -> Vec<&'msg [u8]> {
@@ -68,7 +114,7 @@ fn match_message<'msg>(idx: &usize, rules: &Rules, msg: &'msg [u8]) -> Vec<&'msg
 
     match rule {
         Rule::Lit(b) => {
-            if Some(b) == msg.get(0) {
+            if Some(b) == msg.first() {
                 vec![&msg[1..]]
             } else {
                 vec![]
@@ -85,52 +131,230 @@ fn match_message<'msg>(idx: &usize, rules: &Rules, msg: &'msg [u8]) -> Vec<&'msg
     }
 }
 
-type Rules = HashMap<usize, Rule>;
-type Input = (Rules, Vec<Vec<u8>>);
+pub type Rules = HashMap<usize, Rule>;
+
+/// A rule which refers back to itself before matching any character, which
+/// `match_message` would follow forever
+fn left_recursive(rules: &Rules) -> Option<usize> {
+    // Rules which the first character of a message is matched against
+    let firsts = |idx: usize| -> Vec<usize> {
+        match rules.get(&idx) {
+            Some(Rule::Series(a)) => a.iter().take(1).copied().collect(),
+            Some(Rule::Or(a, b)) => a.iter().take(1).chain(b.iter().take(1)).copied().collect(),
+            _ => Vec::new(),
+        }
+    };
+
+    let mut indices: Vec<usize> = rules.keys().copied().collect();
+    indices.sort_unstable();
 
-fn parse(mut input: impl BufRead) -> Input {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+    indices.into_iter().find(|&start| {
+        let mut seen = HashSet::new();
+        let mut next = firsts(start);
 
+        while let Some(idx) = next.pop() {
+            if idx == start {
+                return true;
+            }
+            if seen.insert(idx) {
+                next.extend(firsts(idx));
+            }
+        }
+
+        false
+    })
+}
+pub type Input = (Rules, Vec<Vec<u8>>);
+
+pub fn parse(input: impl BufRead) -> Result<Input> {
     let mut rules = HashMap::new();
+    let mut rule_lines = HashMap::new();
+    let mut blocks = blocks(input);
+
+    if let Some(block) = blocks.next() {
+        for (n, line) in block?.lines() {
+            let (idx, rule) = parse_rule(line).on_line(n)?;
+            rules.insert(idx, rule);
+            rule_lines.insert(idx, n);
+        }
+    }
+
     let mut messages = Vec::new();
-    let mut parsing_rules = true;
 
-    for line in input_str.lines() {
-        if line.is_empty() {
-            parsing_rules = false;
-            continue;
-        }
+    for block in blocks {
+        messages.extend(block?.lines.into_iter().map(String::into_bytes));
+    }
 
-        if parsing_rules {
-            let (idx, rule) = line.split_once(": ").unwrap();
-            rules.insert(idx.parse().unwrap(), rule.parse().unwrap());
-        } else {
-            messages.push(line.as_bytes().to_vec());
+    for (idx, rule) in &rules {
+        if let Some(undefined) = rule.references().find(|r| !rules.contains_key(r)) {
+            return Err(
+                Error::parse(format!("Undefined rule {}", undefined)).on_line(rule_lines[idx])
+            );
         }
     }
 
-    (rules, messages)
+    if let Some(idx) = left_recursive(&rules) {
+        return Err(Error::parse(format!(
+            "Rule {} refers to itself before matching any character",
+            idx
+        ))
+        .on_line(rule_lines[&idx]));
+    }
+
+    Ok((rules, messages))
 }
 
-fn part_1((rules, msgs): Input) -> usize {
+fn count_matches(rules: &Rules, msgs: &[Vec<u8>]) -> usize {
     msgs.iter()
-        .filter(|msg| match_message(&0, &rules, msg).pop() == Some(&[]))
+        .filter(|msg| match_message(&0, rules, msg).pop() == Some(&[]))
         .count()
 }
 
-fn part_2((mut rules, msgs): Input) -> usize {
+pub fn part_1((rules, msgs): Input) -> Option<usize> {
+    if !rules.contains_key(&0) {
+        return None;
+    }
+
+    Some(count_matches(&rules, &msgs))
+}
+
+pub fn part_2((mut rules, msgs): Input) -> Option<usize> {
+    if [0, 31, 42].iter().any(|idx| !rules.contains_key(idx)) {
+        return None;
+    }
+
     rules.insert(8, Rule::Or(vec![42], vec![42, 8]));
     rules.insert(11, Rule::Or(vec![42, 31], vec![42, 11, 31]));
 
-    msgs.iter()
-        .filter(|msg| match_message(&0, &rules, msg).pop() == Some(&[]))
-        .count()
+    // Rules 42 and 31 could refer to the new ones
+    if left_recursive(&rules).is_some() {
+        return None;
+    }
+
+    Some(count_matches(&rules, &msgs))
+}
+
+/// Rules and `size` messages, shaped like the puzzle: `0: 8 11`, `8: 42` and
+/// `11: 42 31`, with rules 42 and 31 matching complementary sets of messages
+/// of 8 characters.
+///
+/// Such a pair of rules for messages of `2n` characters is built from pairs
+/// `(x, y)` and `(p, q)` for `n` characters, as `x p | y q` and `x q | y p`.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut lits = ['a', 'b'];
+    rng.shuffle(&mut lits);
+
+    // Alternatives of the rules which aren't literals, by local index: 0 and 1
+    // are the literals, and the other rules come after them
+    let mut rules: Vec<[[usize; 2]; 2]> = Vec::new();
+
+    // Pairs of complementary rules, with whether each message of their length
+    // (as bits, most significant first) matches the first rule of the pair
+    let mut pairs: Vec<(usize, usize, Vec<bool>)> = vec![(0, 1, vec![true, false])];
+    let mut len = 1;
+
+    for count in [4, 4, 1].iter() {
+        pairs = (0..*count)
+            .map(|_| {
+                let (x, y, in_x) = rng.choose(&pairs).clone();
+                let (mut p, mut q, mut in_p) = rng.choose(&pairs).clone();
+
+                if rng.ratio(1, 2) {
+                    std::mem::swap(&mut p, &mut q);
+                    in_p.iter_mut().for_each(|m| *m = !*m);
+                }
+
+                rules.push([[x, p], [y, q]]);
+                rules.push([[x, q], [y, p]]);
+
+                let members = (0..1 << (2 * len))
+                    .map(|msg: usize| in_x[msg >> len] == in_p[msg & ((1 << len) - 1)])
+                    .collect();
+
+                (rules.len(), rules.len() + 1, members)
+            })
+            .collect();
+
+        len *= 2;
+    }
+
+    let (rule_42, rule_31, in_42) = pairs.pop().unwrap();
+
+    let mut numbers: Vec<usize> = (1..rules.len() + 20)
+        .filter(|n| ![8, 11, 31, 42].contains(n))
+        .collect();
+    rng.shuffle(&mut numbers);
+    numbers[rule_42] = 42;
+    numbers[rule_31] = 31;
+
+    let mut lines = vec![
+        "0: 8 11".to_string(),
+        "8: 42".to_string(),
+        "11: 42 31".to_string(),
+    ];
+
+    for (i, lit) in lits.iter().enumerate() {
+        lines.push(format!("{}: \"{}\"", numbers[i], lit));
+    }
+
+    for (i, [a, b]) in rules.iter().enumerate() {
+        lines.push(format!(
+            "{}: {} {} | {} {}",
+            numbers[i + 2],
+            numbers[a[0]],
+            numbers[a[1]],
+            numbers[b[0]],
+            numbers[b[1]]
+        ));
+    }
+
+    rng.shuffle(&mut lines);
+
+    let block = |rng: &mut Rng, matches_42: bool| -> String {
+        let msg = loop {
+            let msg = rng.index(1 << len);
+            if in_42[msg] == matches_42 {
+                break msg;
+            }
+        };
+
+        (0..len).rev().map(|bit| lits[msg >> bit & 1]).collect()
+    };
+
+    let messages: Vec<String> = (0..size)
+        .map(|_| {
+            // Counts of blocks matching 42 then 31: matching part 1, only part
+            // 2, or neither
+            let (count_42, count_31) = match rng.index(10) {
+                0..=2 => (2, 1),
+                3..=5 => {
+                    let count_42 = rng.range(2..6);
+                    (count_42, rng.range(1..count_42))
+                }
+                6..=7 => {
+                    let count_42 = rng.range(1..5);
+                    (count_42, count_42 + rng.range(0..2))
+                }
+                8 => (0, rng.range(1..4)),
+                _ => {
+                    let len = rng.range(1..4 * len as u64) as usize;
+                    return (0..len).map(|_| *rng.choose(&lits)).collect();
+                }
+            };
+
+            let mut message: String = (0..count_42).map(|_| block(rng, true)).collect();
+            message.extend((0..count_31).map(|_| block(rng, false)));
+            message
+        })
+        .collect();
+
+    format!("{}\n\n{}\n", lines.join("\n"), messages.join("\n"))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     #[test]
     fn part_1_example() {
@@ -148,8 +372,8 @@ aaabbb
 aaaabbb
 ";
 
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_1(input), 2);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_1(input), Some(2));
     }
 
     #[test]
@@ -204,8 +428,37 @@ aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba
 }
 ";
 
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_1(input.clone()), 3);
-        assert_eq!(part_2(input), 12);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_1(input.clone()), Some(3));
+        assert_eq!(part_2(input), Some(12));
+    }
+
+    #[test]
+    fn self_referencing_rules() {
+        let error = parse(io::Cursor::new("0: 0\n\na\n")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1: Rule 0 refers to itself before matching any character"
+        );
+
+        let error = parse(io::Cursor::new("0: 1 2\n1: \"a\"\n2: 3 | 1\n3: 2 1\n")).unwrap_err();
+        assert!(error.to_string().starts_with("line 3: Rule 2"));
+
+        // Matching a character first is fine
+        let input = parse(io::Cursor::new("0: 1 | 1 0\n1: \"a\"\n\naaa\nab\n")).unwrap();
+        assert_eq!(part_1(input), Some(1));
+
+        // Neither are the new rules of part 2, unless 42 refers to them (when
+        // they are different in the input)
+        let input = parse(io::Cursor::new(
+            "0: 8 11\n8: 42\n11: 42 31\n42: \"a\"\n31: \"b\"\n",
+        ))
+        .unwrap();
+        assert_eq!(part_2(input), Some(0));
+        let input = parse(io::Cursor::new(
+            "0: 42 31\n8: 1\n42: 8\n1: \"a\"\n31: \"b\"\n",
+        ))
+        .unwrap();
+        assert_eq!(part_2(input), None);
     }
 }
@@ -1,105 +1,119 @@
+//! Day 20: Jurassic Jigsaw
+
 use std::fmt::Debug;
-use std::io;
 use std::io::prelude::*;
 
 use std::collections::HashMap;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use serde::{Deserialize, Serialize};
 
-    let solution = match part {
-        1 => part_1(input),
-        2 => part_2(input),
-        _ => unimplemented!(),
-    };
+use crate::error::Context;
+use crate::grid::{self, Grid};
+use crate::parallel;
+use crate::parsing::{blocks, Block};
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    const DAY: u8 = 20;
+
+    type Input = Tiles;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    println!("{}", solution);
+    fn part_1(tiles: Self::Input) -> Result<Self::Part1> {
+        part_1(tiles).ok_or(Error::NoSolution)
+    }
 
-    Ok(())
+    fn part_2(tiles: Self::Input) -> Result<Self::Part2> {
+        part_2(tiles).ok_or(Error::NoSolution)
+    }
 }
 
-type TileId = usize;
-type EdgeIdx = u8;
-type Pixel = bool;
-type Edge = Vec<Pixel>;
+impl Generate for Day20 {
+    const INPUT_SIZE: usize = 144;
 
-#[derive(Clone)]
-struct Tile(Vec<Vec<Pixel>>);
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+pub type TileId = usize;
+pub type EdgeIdx = u8;
+pub type Pixel = bool;
+pub type Edge = Vec<Pixel>;
+
+/// Square image, made of rows of pixels
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tile(Grid<Pixel>);
 
 impl Debug for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        for row in self.0.iter() {
-            for cell in row {
-                f.write_str(if *cell { "#" } else { "." })?;
-            }
-            f.write_str("\n")?;
-        }
-
-        Ok(())
+        write!(f, "{}", self.0)
     }
 }
 
 impl Tile {
-    /// Rotate 90deg anti-clockwise
-    fn rotate(&self) -> Self {
-        let mut tile = Vec::new();
-
-        let n = self.0.len();
+    /// Panics if the rows don't make a square
+    pub fn new(rows: Vec<Vec<Pixel>>) -> Self {
+        let n = rows.len();
+        assert!(
+            rows.iter().all(|row| row.len() == n),
+            "Tiles must be square"
+        );
+        Self(Grid::new(n, rows.into_iter().flatten().collect()))
+    }
 
-        for _ in 0..n {
-            let j = n - 1 - tile.len();
-            tile.push(self.0.iter().map(|r| r[j]).collect());
-        }
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[Pixel]> {
+        self.0.rows()
+    }
 
-        Self(tile)
+    /// Rotate 90deg anti-clockwise
+    pub fn rotate(&self) -> Self {
+        Self(self.0.rotate())
     }
 
     /// Flip in the vertical direction (around the horizontal axis)
-    fn _flip_v(&self) -> Self {
-        Self(self.0.iter().cloned().rev().collect())
+    pub fn flip_v(&self) -> Self {
+        Self(self.0.flip_v())
     }
 
     /// Flip in the horizontal direction (around the vertical axis)
-    fn flip_h(&self) -> Self {
-        Self(
-            self.0
-                .iter()
-                .cloned()
-                .map(|r| r.into_iter().rev().collect())
-                .collect(),
-        )
-    }
-
-    // N, W, S, E
-    // N: right to left
-    // W: top to bottom
-    // S: left to right
-    // E: bottom to top
-    fn edges(&self) -> Vec<Edge> {
-        let n = self.0.len();
-        assert_eq!(self.0[0].len(), n);
-        assert_eq!(self.0[n - 1].len(), n);
+    pub fn flip_h(&self) -> Self {
+        Self(self.0.flip_h())
+    }
+
+    /// Edges in anti-clockwise order:
+    /// - N: right to left
+    /// - W: top to bottom
+    /// - S: left to right
+    /// - E: bottom to top
+    pub fn edges(&self) -> Vec<Edge> {
+        let n = self.0.width();
+        assert_eq!(self.0.height(), n);
 
         vec![
-            self.0[0].iter().rev().copied().collect(),
-            self.0.iter().map(|r| r[0]).collect(),
-            self.0[n - 1].clone(),
-            self.0.iter().map(|r| r[n - 1]).rev().collect(),
+            self.0.row(0).iter().rev().copied().collect(),
+            self.0.column(0).copied().collect(),
+            self.0.row(n - 1).to_vec(),
+            self.0.column(n - 1).rev().copied().collect(),
         ]
     }
 
-    fn remove_edges(&mut self) {
-        let _ = self.0.pop();
-        let _ = self.0.remove(0);
-
-        for row in self.0.iter_mut() {
-            let _ = row.pop();
-            let _ = row.remove(0);
-        }
+    fn remove_edges(&self) -> Self {
+        let n = self.0.width();
+        Self(self.0.sub_grid((1, 1), n - 2, n - 2))
     }
 
-    fn orient(&mut self, mut top: EdgeIdx, reversed: bool) {
-        while top % 4 != 0 {
+    /// Rotate so that the edge `top` ends up on top, then flip if `reversed`
+    pub fn orient(&mut self, mut top: EdgeIdx, reversed: bool) {
+        while !top.is_multiple_of(4) {
             *self = self.rotate();
             top += 1;
         }
@@ -109,20 +123,22 @@ impl Tile {
         }
     }
 
-    fn find_pattern(&self, pattern: &Tile) -> usize {
+    /// Number of times the set pixels of `pattern` are found in this tile, 0
+    /// when the pattern is bigger than the tile
+    pub fn find_pattern(&self, pattern: &Tile) -> usize {
+        let (width, height) = (self.0.width(), self.0.height());
+        if pattern.0.width() > width || pattern.0.height() > height {
+            return 0;
+        }
+
         let mut count = 0;
 
-        for y_off in 0..self.0.len() - pattern.0.len() {
-            for x_off in 0..self.0[0].len() - pattern.0[0].len() {
-                let pat_match = pattern.0.iter().enumerate().all(|(y, row)| {
-                    row.iter().enumerate().all(|(x, pat)| {
-                        if *pat {
-                            self.0[y + y_off][x + x_off]
-                        } else {
-                            true
-                        }
-                    })
-                });
+        for y_off in 0..=height - pattern.0.height() {
+            for x_off in 0..=width - pattern.0.width() {
+                let pat_match = pattern
+                    .0
+                    .iter()
+                    .all(|((x, y), pat)| !*pat || self.0[(x + x_off, y + y_off)]);
 
                 if pat_match {
                     count += 1;
@@ -133,47 +149,63 @@ impl Tile {
         count
     }
 
-    fn count_set_pixels(&self) -> usize {
-        self.0
-            .iter()
-            .map(|r| r.iter().filter(|p| **p).count())
-            .sum()
+    pub fn count_set_pixels(&self) -> usize {
+        self.0.cells().iter().filter(|p| **p).count()
     }
 }
 
-type Tiles = HashMap<TileId, Tile>;
+pub type Tiles = HashMap<TileId, Tile>;
+
+/// Width and height of the tiles
+pub const TILE_SIZE: usize = 10;
+
+fn parse_row(line: &str) -> Result<Vec<Pixel>> {
+    if line.len() != TILE_SIZE {
+        return Err(Error::parse(format!(
+            "Expected rows of {} pixels",
+            TILE_SIZE
+        )));
+    }
+
+    grid::parse_row(line)
+}
+
+fn parse_tile(block: &Block) -> Result<(TileId, Tile)> {
+    let id = block.lines[0]
+        .strip_prefix("Tile ")
+        .and_then(|id| id.strip_suffix(':'))
+        .ok_or_else(|| Error::parse("Expected \"Tile <id>:\"").on_line(block.first_line))?;
+    let id = id.parse::<TileId>().at(block.first_line, 6)?;
 
-fn parse(mut input: impl BufRead) -> Tiles {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+    let mut tile = Grid::default();
 
+    for row in block.body().records(parse_row) {
+        tile.push_row(row?)?;
+    }
+
+    if tile.height() != TILE_SIZE {
+        return Err(Error::parse(format!("Expected {} rows", TILE_SIZE)).on_line(block.first_line));
+    }
+
+    Ok((id, Tile(tile)))
+}
+
+pub fn parse(input: impl BufRead) -> Result<Tiles> {
     let mut tiles = HashMap::new();
-    let mut tile = Vec::new();
-    let mut tile_id = None;
-
-    for line in input_str.lines() {
-        if line.is_empty() {
-            if let Some(id) = tile_id {
-                let mut finished_tile = Vec::new();
-                std::mem::swap(&mut finished_tile, &mut tile);
-                tiles.insert(id, Tile(finished_tile));
-            }
-        } else if let Some(id) = line.strip_prefix("Tile ") {
-            let id = id.strip_suffix(":").unwrap();
-            let id = id.parse::<usize>().unwrap();
-            tile_id = Some(id);
-        } else {
-            let row: Vec<Pixel> = line.as_bytes().iter().map(|b| b == &b'#').collect();
-            tile.push(row);
+
+    for block in blocks(input) {
+        let block = block?;
+        let (id, tile) = parse_tile(&block)?;
+
+        if tiles.insert(id, tile).is_some() {
+            return Err(Error::parse(format!("Duplicate tile {}", id)).on_line(block.first_line));
         }
     }
 
-    tiles.insert(tile_id.unwrap(), Tile(tile));
-
-    tiles
+    Ok(tiles)
 }
 
-type EdgeId = (TileId, EdgeIdx, bool);
+pub type EdgeId = (TileId, EdgeIdx, bool);
 
 fn edge_map(tiles: &Tiles) -> HashMap<Edge, Vec<EdgeId>> {
     let mut edges = HashMap::new();
@@ -201,9 +233,8 @@ fn unique_edges(edges: &HashMap<Edge, Vec<EdgeId>>) -> HashMap<TileId, HashMap<E
 
     for mut edge_ids in edges
         .values()
-        .cloned()
-        .into_iter()
         .filter(|edge_ids| edge_ids.len() == 1)
+        .cloned()
     {
         // Edge with no connections
         let (tile_id, edge_id, reversed) = edge_ids.pop().unwrap();
@@ -224,13 +255,19 @@ fn tiles_with_free_edges(
         .filter(move |(_, edge_set)| edge_set.len() == free_edge_count)
 }
 
-fn part_1(tiles: Tiles) -> usize {
+pub fn part_1(tiles: Tiles) -> Option<usize> {
     let edges = edge_map(&tiles);
     let unique = unique_edges(&edges);
 
-    tiles_with_free_edges(&unique, 2)
+    let corners: Vec<&TileId> = tiles_with_free_edges(&unique, 2)
         .map(|(tile_id, _)| tile_id)
-        .product()
+        .collect();
+
+    if corners.len() != 4 {
+        return None;
+    }
+
+    Some(corners.into_iter().product())
 }
 
 fn search_for_edge(
@@ -238,7 +275,7 @@ fn search_for_edge(
     neighbour_id: &TileId,
     neighbour: &Tile,
     neighbour_edge_ix: &EdgeIdx,
-) -> EdgeId {
+) -> Option<EdgeId> {
     // Reversing so that they match side by side (anti-clockwise indexing)
     let common_edge: Edge = Tile::edges(neighbour)[*neighbour_edge_ix as usize]
         .iter()
@@ -246,21 +283,26 @@ fn search_for_edge(
         .copied()
         .collect();
 
-    *edges
-        .get(&common_edge)
-        .unwrap()
+    edges
+        .get(&common_edge)?
         .iter()
         .find(|(tile_id, _, _)| tile_id != neighbour_id)
-        .unwrap()
+        .copied()
 }
 
-fn solve_puzzle(tiles: Tiles) -> Tile {
+/// Assemble the tiles into a single picture, without their edges
+pub fn solve_puzzle(tiles: Tiles) -> Option<Tile> {
     let edges = edge_map(&tiles);
     let unique = unique_edges(&edges);
 
     let width = f32::sqrt(tiles.len() as f32) as usize;
     let height = width; // It's a square!
 
+    // Without any tile there is no picture
+    if width == 0 || width * height != tiles.len() {
+        return None;
+    }
+
     let mut row = Vec::new();
     let mut grid = Vec::new();
 
@@ -275,18 +317,18 @@ fn solve_puzzle(tiles: Tiles) -> Tile {
                 (None, None) => {
                     // Using an arbitrary corner as the top left corner
                     let (top_left_id, top_left_free_edges) =
-                        tiles_with_free_edges(&unique, 2).next().unwrap();
-                    let mut top_left = tiles.get(&top_left_id).unwrap().clone();
+                        tiles_with_free_edges(&unique, 2).next()?;
+                    let mut top_left = tiles.get(top_left_id)?.clone();
 
                     let mut top_left_free_edges: Vec<EdgeIdx> =
-                        top_left_free_edges.iter().map(|(e, _)| *e).collect();
+                        top_left_free_edges.keys().copied().collect();
                     top_left_free_edges.sort_unstable();
 
                     // Reorient top_left
                     match top_left_free_edges[..] {
                         [0, 3] => top_left.orient(3, false),
                         [top, _] => top_left.orient(top, false),
-                        _ => unreachable!(),
+                        _ => return None,
                     }
                     (*top_left_id, top_left)
                 }
@@ -294,9 +336,9 @@ fn solve_puzzle(tiles: Tiles) -> Tile {
                 // Top row
                 (Some((left_neighbour_id, left_neighbour)), None) => {
                     let (tile_id, left_edge_idx, left_reversed) =
-                        search_for_edge(&edges, left_neighbour_id, left_neighbour, &3);
+                        search_for_edge(&edges, left_neighbour_id, left_neighbour, &3)?;
 
-                    let mut tile = tiles.get(&tile_id).unwrap().clone();
+                    let mut tile = tiles.get(&tile_id)?.clone();
 
                     if left_reversed {
                         tile.orient(left_edge_idx + 1, true);
@@ -309,9 +351,9 @@ fn solve_puzzle(tiles: Tiles) -> Tile {
                 // New row, first element
                 (None, Some((top_neighbour_id, top_neighbour))) => {
                     let (tile_id, top_edge_idx, top_reversed) =
-                        search_for_edge(&edges, top_neighbour_id, top_neighbour, &2);
+                        search_for_edge(&edges, top_neighbour_id, top_neighbour, &2)?;
 
-                    let mut tile = tiles.get(&tile_id).unwrap().clone();
+                    let mut tile = tiles.get(&tile_id)?.clone();
                     tile.orient(top_edge_idx, top_reversed);
 
                     (tile_id, tile)
@@ -321,11 +363,11 @@ fn solve_puzzle(tiles: Tiles) -> Tile {
                     Some((top_neighbour_id, top_neighbour)),
                 ) => {
                     let (tile_id, _, left_reversed) =
-                        search_for_edge(&edges, left_neighbour_id, left_neighbour, &3);
+                        search_for_edge(&edges, left_neighbour_id, left_neighbour, &3)?;
 
-                    let (_, top, _) = search_for_edge(&edges, top_neighbour_id, top_neighbour, &2);
+                    let (_, top, _) = search_for_edge(&edges, top_neighbour_id, top_neighbour, &2)?;
 
-                    let mut tile = tiles.get(&tile_id).unwrap().clone();
+                    let mut tile = tiles.get(&tile_id)?.clone();
 
                     tile.orient(top, left_reversed);
 
@@ -342,57 +384,171 @@ fn solve_puzzle(tiles: Tiles) -> Tile {
     }
 
     // Remove all tile edges and merge into a big picture
-    let mut picture: Vec<Vec<Pixel>> = std::iter::repeat(Vec::new())
-        .take(height * (10 - 2))
-        .collect();
+    let mut picture = Grid::default();
 
-    for (row_ix, row) in grid.into_iter().enumerate() {
-        for (_, mut tile) in row {
-            tile.remove_edges();
+    for row in grid {
+        let row: Vec<Tile> = row.iter().map(|(_, tile)| tile.remove_edges()).collect();
 
-            for (tile_row_ix, mut tile_row) in tile.0.into_iter().enumerate() {
-                picture[row_ix * (10 - 2) + tile_row_ix].append(&mut tile_row);
-            }
+        for y in 0..TILE_SIZE - 2 {
+            picture
+                .push_row(row.iter().flat_map(|tile| tile.0.row(y)).copied())
+                .ok()?;
         }
     }
 
-    Tile(picture)
+    Some(Tile(picture))
 }
 
-fn part_2(tiles: Tiles) -> usize {
-    let mut picture = solve_puzzle(tiles);
+const SEA_MONSTER: &str = "\
+..................#.
+#....##....##....###
+.#..#..#..#..#..#...";
 
-    let sea_monster_raw = "                  # \n\
-#    ##    ##    ###
- #  #  #  #  #  #   ";
+pub fn part_2(tiles: Tiles) -> Option<usize> {
+    let mut picture = solve_puzzle(tiles)?;
 
-    let sea_monster = Tile(
-        sea_monster_raw
-            .lines()
-            .map(|l| l.as_bytes().iter().map(|b| b == &b'#').collect())
-            .collect(),
-    );
+    let sea_monster = Tile(Grid::parse(SEA_MONSTER.as_bytes()).expect("Valid sea monster"));
 
-    let mut max_pattern_count = 0;
+    let mut orientations = Vec::with_capacity(8);
     // For each rotation
     for _ in 0..=3 {
         // For each flip
         for _ in 0..=1 {
-            max_pattern_count = max_pattern_count.max(picture.find_pattern(&sea_monster));
+            orientations.push(picture.clone());
             picture = picture.flip_h();
         }
         picture = picture.rotate();
     }
 
+    let max_pattern_count =
+        parallel::map(&orientations, |picture| picture.find_pattern(&sea_monster))
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+
     let sea_monster_set_pixel_count = sea_monster.count_set_pixels();
     let picture_set_pixel_count = picture.count_set_pixels();
 
-    picture_set_pixel_count - max_pattern_count * sea_monster_set_pixel_count
+    Some(picture_set_pixel_count - max_pattern_count * sea_monster_set_pixel_count)
+}
+
+/// About `size` tiles (a square of 3 by 3 up to 12 by 12 of them), which assemble in a
+/// single way into a picture with some sea monsters.
+///
+/// Neighbouring tiles share their edge, and no other edge (in either
+/// direction) is the same, nor a palindrome.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let side = ((size as f64).sqrt() as usize).clamp(3, 12);
+    let inner = TILE_SIZE - 2;
+    let width = side * inner;
+
+    let mut picture = Grid::new(width, (0..width * width).map(|_| rng.ratio(1, 4)).collect());
+    let sea_monster = Grid::<bool>::parse(SEA_MONSTER.as_bytes()).expect("Valid sea monster");
+    let mut monsters: Vec<grid::Pos> = Vec::new();
+
+    for _ in 0..side * side {
+        let x = rng.index(width - sea_monster.width() + 1);
+        let y = rng.index(width - sea_monster.height() + 1);
+
+        let overlaps = monsters.iter().any(|&(mx, my)| {
+            x < mx + sea_monster.width()
+                && mx < x + sea_monster.width()
+                && y < my + sea_monster.height()
+                && my < y + sea_monster.height()
+        });
+
+        if overlaps || monsters.len() == side {
+            continue;
+        }
+
+        for ((dx, dy), &set) in sea_monster.iter() {
+            if set {
+                picture[(x + dx, y + dy)] = true;
+            }
+        }
+
+        monsters.push((x, y));
+    }
+
+    // Edges go through the corners shared by up to 4 tiles
+    let corners = Grid::new(
+        side + 1,
+        (0..(side + 1) * (side + 1))
+            .map(|_| rng.ratio(1, 2))
+            .collect(),
+    );
+    let mut used: Vec<Edge> = Vec::new();
+
+    let mut edge = |rng: &mut Rng, from: grid::Pos, to: grid::Pos| -> Edge {
+        loop {
+            let mut edge = vec![corners[from]];
+            edge.extend((0..inner).map(|_| rng.ratio(1, 2)));
+            edge.push(corners[to]);
+
+            let reversed: Edge = edge.iter().rev().copied().collect();
+
+            if edge != reversed && !used.contains(&edge) {
+                used.push(reversed);
+                used.push(edge.clone());
+                return edge;
+            }
+        }
+    };
+
+    // Edges from left to right, and from top to bottom
+    let horizontal = Grid::new(
+        side,
+        (0..side * (side + 1))
+            .map(|i| (i % side, i / side))
+            .map(|(x, y)| edge(rng, (x, y), (x + 1, y)))
+            .collect(),
+    );
+    let vertical = Grid::new(
+        side + 1,
+        (0..(side + 1) * side)
+            .map(|i| (i % (side + 1), i / (side + 1)))
+            .map(|(x, y)| edge(rng, (x, y), (x, y + 1)))
+            .collect(),
+    );
+
+    let mut ids: Vec<TileId> = (1000..10000).collect();
+    rng.shuffle(&mut ids);
+
+    let mut tiles: Vec<String> = ids
+        .iter()
+        .zip((0..side * side).map(|i| (i % side, i / side)))
+        .map(|(id, (x, y))| {
+            let last = TILE_SIZE - 1;
+            let pixels = (0..TILE_SIZE * TILE_SIZE)
+                .map(|i| (i % TILE_SIZE, i / TILE_SIZE))
+                .map(|(px, py)| match (px, py) {
+                    (_, 0) => horizontal[(x, y)][px],
+                    (_, _) if py == last => horizontal[(x, y + 1)][px],
+                    (0, _) => vertical[(x, y)][py],
+                    (_, _) if px == last => vertical[(x + 1, y)][py],
+                    _ => picture[(x * inner + px - 1, y * inner + py - 1)],
+                });
+
+            let mut tile = Tile(Grid::new(TILE_SIZE, pixels.collect()));
+            for _ in 0..rng.index(4) {
+                tile = tile.rotate();
+            }
+            if rng.ratio(1, 2) {
+                tile = tile.flip_h();
+            }
+
+            format!("Tile {}:\n{}", id, tile.0)
+        })
+        .collect();
+
+    rng.shuffle(&mut tiles);
+    tiles.join("\n")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "Tile 2311:
 ..##.#..#.
@@ -505,13 +661,51 @@ Tile 3079:
 
     #[test]
     fn part_1_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_1(input), 20899048083289);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_1(input), Some(20899048083289));
     }
 
     #[test]
     fn part_2_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_2(input), 273);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_2(input), Some(273));
+    }
+
+    #[test]
+    fn no_tiles() {
+        let input = parse(io::Cursor::new("")).unwrap();
+        assert_eq!(part_1(input.clone()), None);
+        assert_eq!(part_2(input), None);
+    }
+
+    #[test]
+    fn smaller_than_a_sea_monster() {
+        let mut tiles = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        tiles.retain(|id, _| [1951, 2311, 2729, 1427].contains(id));
+
+        assert_eq!(part_1(tiles.clone()), Some(1951 * 2311 * 2729 * 1427));
+        let picture = solve_puzzle(tiles.clone()).unwrap();
+        assert_eq!(part_2(tiles), Some(picture.count_set_pixels()));
+    }
+
+    #[test]
+    fn patterns_at_the_edges() {
+        let tile = Tile::new(vec![vec![false, false], vec![false, true]]);
+        assert_eq!(tile.find_pattern(&Tile::new(vec![vec![true]])), 1);
+        assert_eq!(tile.find_pattern(&tile), 1);
+    }
+
+    #[test]
+    fn tile_rows() {
+        let rows = vec![vec![true, false], vec![false, false]];
+        let tile = Tile::new(rows.clone()).rotate();
+        let rotated: Vec<_> = tile.rows().map(<[Pixel]>::to_vec).collect();
+        assert_eq!(rotated, vec![vec![false, false], vec![true, false]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tiles must be square")]
+    fn non_square_tile() {
+        Tile::new(vec![vec![true, false]]);
     }
 }
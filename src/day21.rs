@@ -1,38 +1,91 @@
-use std::io;
+//! Day 21: Allergen Assessment
+
 use std::io::prelude::*;
+use std::iter::FromIterator;
 
 use std::collections::{HashMap, HashSet};
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let mut buffer = String::new();
-    let input = parse(input, &mut buffer);
+use crate::error::column;
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
 
-    match part {
-        1 => println!("{}", part_1(input)),
-        2 => println!("{}", part_2(input)),
-        _ => unimplemented!(),
-    };
+pub struct Day21;
+
+impl Solution for Day21 {
+    const DAY: u8 = 21;
+
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = String;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        part_1(input).ok_or(Error::NoSolution)
+    }
 
-    Ok(())
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        part_2(input).ok_or(Error::NoSolution)
+    }
 }
 
-type Input<'a> = Vec<(HashSet<&'a str>, Vec<&'a str>)>;
+impl Generate for Day21 {
+    const INPUT_SIZE: usize = 40;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
 
-fn parse(mut input: impl BufRead, mut buffer: &mut String) -> Input {
-    input.read_to_string(&mut buffer).unwrap();
+pub type Input = Vec<(HashSet<String>, Vec<String>)>;
 
-    buffer
-        .lines()
-        .map(|line| {
-            let (ingredients, allergens) = line.split_once(" (contains ").unwrap();
-            let ingredients = ingredients.split(' ').collect();
-            let allergens = allergens.strip_suffix(')').unwrap().split(", ").collect();
-            (ingredients, allergens)
+/// Names in `list` (a slice of `line`) separated by `separator`, none of them
+/// empty
+fn names<C: FromIterator<String>>(
+    line: &str,
+    list: &str,
+    separator: &str,
+    expected: &str,
+) -> Result<C> {
+    list.split(separator)
+        .map(|name| match name {
+            "" => Err(Error::parse(format!("Expected {}", expected)).at_column(column(line, name))),
+            _ => Ok(name.to_string()),
         })
         .collect()
 }
 
-fn match_ingredients(input: Input) -> (HashMap<&str, usize>, HashSet<&str>, HashMap<&str, &str>) {
+fn parse_food(line: &str) -> Result<(HashSet<String>, Vec<String>)> {
+    let (ingredients, allergens) = line
+        .split_once(" (contains ")
+        .ok_or_else(|| Error::parse("Expected \" (contains \" after the ingredients"))?;
+    let allergens = allergens
+        .strip_suffix(')')
+        .ok_or_else(|| Error::parse("Expected ')'").at_column(line.len() + 1))?;
+
+    Ok((
+        names(line, ingredients, " ", "an ingredient")?,
+        names(line, allergens, ", ", "an allergen")?,
+    ))
+}
+
+pub fn parse(input: impl BufRead) -> Result<Input> {
+    records(input, parse_food).collect()
+}
+
+/// Count of each ingredient, the ingredients with an allergen and the
+/// ingredient of each allergen
+type Matches<'a> = (
+    HashMap<&'a str, usize>,
+    HashSet<&'a str>,
+    HashMap<&'a str, &'a str>,
+);
+
+/// `None` when elimination can't find the ingredients of all the allergens
+fn match_ingredients(input: &Input) -> Option<Matches<'_>> {
     let mut candidates = HashMap::new();
     let mut all_ingredients = HashMap::new();
     let mut solved_ingredient = HashSet::new();
@@ -40,21 +93,28 @@ fn match_ingredients(input: Input) -> (HashMap<&str, usize>, HashSet<&str>, Hash
 
     for (ingredients, allergens) in input.iter() {
         for allergen in allergens {
-            let entry = candidates
-                .entry(*allergen)
-                .or_insert_with(|| ingredients.clone());
-            *entry = entry.intersection(ingredients).copied().collect();
+            let entry: &mut HashSet<&str> = candidates
+                .entry(allergen.as_str())
+                .or_insert_with(|| ingredients.iter().map(String::as_str).collect());
+            entry.retain(|ingredient| ingredients.contains(*ingredient));
         }
 
         for ingredient in ingredients {
-            let entry = all_ingredients.entry(*ingredient).or_insert(0);
+            let entry = all_ingredients.entry(ingredient.as_str()).or_insert(0);
             *entry += 1;
         }
     }
 
+    // Allergens solved and candidates left, which each pass has to change
+    let progress = |candidates: &HashMap<&str, HashSet<&str>>, solved: usize| {
+        (solved, candidates.values().map(HashSet::len).sum::<usize>())
+    };
+
     while solved_allergen.len() < candidates.len() {
+        let before = progress(&candidates, solved_allergen.len());
+
         for (&allergen, ingredients) in candidates.iter_mut() {
-            if solved_allergen.get(allergen).is_none() {
+            if !solved_allergen.contains_key(allergen) {
                 if ingredients.len() == 1 {
                     let ingredient = ingredients.iter().next().unwrap();
                     solved_ingredient.insert(*ingredient);
@@ -68,45 +128,156 @@ fn match_ingredients(input: Input) -> (HashMap<&str, usize>, HashSet<&str>, Hash
                 }
             }
         }
+
+        // Stuck, the ingredients left are all candidates of several allergens
+        if progress(&candidates, solved_allergen.len()) == before {
+            return None;
+        }
     }
 
-    (all_ingredients, solved_ingredient, solved_allergen)
+    Some((all_ingredients, solved_ingredient, solved_allergen))
 }
 
-fn part_1(input: Input) -> usize {
-    let (all_ingredients, solved_ingredient, _) = match_ingredients(input);
+pub fn part_1(input: Input) -> Option<usize> {
+    let (all_ingredients, solved_ingredient, _) = match_ingredients(&input)?;
 
-    all_ingredients
+    let count = all_ingredients
         .iter()
         .filter(|(ingredient, _)| !solved_ingredient.contains(**ingredient))
         .map(|(_, count)| count)
-        .sum()
+        .sum();
+
+    Some(count)
 }
 
-fn part_2(input: Input) -> String {
-    let (_, _, solved_allergens) = match_ingredients(input);
+pub fn part_2(input: Input) -> Option<String> {
+    let (_, _, solved_allergens) = match_ingredients(&input)?;
 
     let mut solved_allergens: Vec<(&str, &str)> = solved_allergens.into_iter().collect();
 
     solved_allergens.sort_unstable_by_key(|(allergen, _)| *allergen);
 
-    let last_index = solved_allergens.len() - 1;
-    let mut dangerous = String::new();
+    let dangerous: Vec<&str> = solved_allergens
+        .into_iter()
+        .map(|(_, ingredient)| ingredient)
+        .collect();
+
+    Some(dangerous.join(","))
+}
+
+const ALLERGENS: [&str; 8] = [
+    "dairy", "eggs", "fish", "nuts", "peanuts", "sesame", "soy", "wheat",
+];
+
+/// `size` foods (or a few more), with each allergen in a single ingredient
+/// which can be found by elimination
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(ALLERGENS.len());
+
+    // The first ingredients contain the allergens, in order
+    let mut names: Vec<String> = Vec::new();
+    while names.len() < 200 {
+        let len = rng.range(4..9);
+        let name = (0..len)
+            .map(|_| (b'a' + rng.range(0..26) as u8) as char)
+            .collect();
+
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    let safe = ALLERGENS.len()..names.len();
+
+    let mut food = |i: usize| {
+        let mut allergens: Vec<usize> = (0..ALLERGENS.len()).collect();
+        rng.shuffle(&mut allergens);
+        allergens.truncate(rng.range(1..4) as usize);
+
+        // Every allergen is listed at least once
+        if i < ALLERGENS.len() && !allergens.contains(&i) {
+            allergens[0] = i;
+        }
 
-    for (i, (_, ingredient)) in solved_allergens.into_iter().enumerate() {
-        dangerous.push_str(ingredient);
+        // Allergens aren't always listed
+        let mut ingredients: Vec<usize> = (0..ALLERGENS.len())
+            .filter(|a| allergens.contains(a) || rng.ratio(1, 3))
+            .collect();
 
-        if i < last_index {
-            dangerous.push(',');
+        for _ in 0..rng.range(10..30) {
+            let ingredient = safe.start + rng.index(safe.len());
+            if !ingredients.contains(&ingredient) {
+                ingredients.push(ingredient);
+            }
         }
+
+        rng.shuffle(&mut ingredients);
+        allergens.sort_unstable();
+        (ingredients, allergens)
+    };
+
+    let mut foods: Vec<(Vec<usize>, Vec<usize>)> = (0..size).map(&mut food).collect();
+
+    while !solvable(&foods) {
+        foods.push(food(foods.len()));
     }
 
-    dangerous
+    foods
+        .iter()
+        .map(|(ingredients, allergens)| {
+            let ingredients: Vec<&str> = ingredients.iter().map(|&i| names[i].as_str()).collect();
+            let allergens: Vec<&str> = allergens.iter().map(|&a| ALLERGENS[a]).collect();
+
+            format!(
+                "{} (contains {})\n",
+                ingredients.join(" "),
+                allergens.join(", ")
+            )
+        })
+        .collect()
+}
+
+/// Whether the ingredient of each allergen can be found by elimination, as
+/// `match_ingredients` does
+fn solvable(foods: &[(Vec<usize>, Vec<usize>)]) -> bool {
+    let mut candidates: Vec<HashSet<usize>> = (0..ALLERGENS.len())
+        .map(|allergen| {
+            foods
+                .iter()
+                .filter(|(_, allergens)| allergens.contains(&allergen))
+                .map(|(ingredients, _)| ingredients.iter().copied().collect::<HashSet<_>>())
+                .reduce(|a, b| a.intersection(&b).copied().collect())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut solved = HashSet::new();
+
+    while solved.len() < candidates.len() {
+        let known = candidates
+            .iter()
+            .filter(|c| c.len() == 1)
+            .flatten()
+            .copied();
+        let newly_solved: HashSet<usize> = known.filter(|i| !solved.contains(i)).collect();
+
+        if newly_solved.is_empty() {
+            return false;
+        }
+
+        solved.extend(newly_solved);
+
+        for ingredients in candidates.iter_mut().filter(|c| c.len() > 1) {
+            ingredients.retain(|i| !solved.contains(i));
+        }
+    }
+
+    true
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)
 trh fvjkl sbzzf mxmxvkd (contains dairy)
@@ -116,15 +287,38 @@ sqjhc mxmxvkd sbzzf (contains fish)
 
     #[test]
     fn part_1_example() {
-        let mut buffer = String::new();
-        let input = parse(io::Cursor::new(EXAMPLE), &mut buffer);
-        assert_eq!(part_1(input), 5);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_1(input), Some(5));
     }
 
     #[test]
     fn part_2_example() {
-        let mut buffer = String::new();
-        let input = parse(io::Cursor::new(EXAMPLE), &mut buffer);
-        assert_eq!(part_2(input), "mxmxvkd,sqjhc,fvjkl");
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_2(input), Some("mxmxvkd,sqjhc,fvjkl".to_string()));
+    }
+
+    #[test]
+    fn ambiguous_allergens() {
+        let input = parse(io::Cursor::new("a b (contains x, y)\n")).unwrap();
+        assert_eq!(part_1(input.clone()), None);
+        assert_eq!(part_2(input), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |text: &str| parse(io::Cursor::new(text)).unwrap_err().to_string();
+        assert_eq!(
+            error("a (contains )"),
+            "line 1, column 13: Expected an allergen"
+        );
+        assert_eq!(
+            error("a b (contains x, , y)"),
+            "line 1, column 18: Expected an allergen"
+        );
+        assert_eq!(
+            error("a  b (contains x)"),
+            "line 1, column 3: Expected an ingredient"
+        );
+        assert!(error("a (contains x").contains("Expected ')'"));
     }
 }
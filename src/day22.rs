@@ -1,49 +1,79 @@
-use std::io;
+//! Day 22: Crab Combat
+
 use std::io::prelude::*;
 
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use crate::error::Context;
+use crate::parsing::blocks;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
 
-    match part {
-        1 => println!("{}", part_1(input)),
-        2 => println!("{}", part_2(input)),
-        _ => unimplemented!(),
-    };
+pub struct Day22;
+
+impl Solution for Day22 {
+    const DAY: u8 = 22;
+
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
 
-    Ok(())
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(input))
+    }
+
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(input))
+    }
 }
 
-type Input = (VecDeque<usize>, VecDeque<usize>);
+impl Generate for Day22 {
+    const INPUT_SIZE: usize = 50;
 
-fn parse(mut input: impl BufRead) -> Input {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
 
-    let mut player_1 = VecDeque::new();
-    let mut player_2 = VecDeque::new();
+pub type Input = (VecDeque<usize>, VecDeque<usize>);
 
-    let mut player_1_done = false;
+pub fn parse(input: impl BufRead) -> Result<Input> {
+    let mut blocks = blocks(input);
+    let mut cards = HashSet::new();
 
-    for line in input_str.lines() {
-        if !line.starts_with("Player") {
-            if line.is_empty() {
-                player_1_done = true;
-            } else if player_1_done {
-                player_2.push_back(line.parse().unwrap())
-            } else {
-                player_1.push_back(line.parse().unwrap())
+    let mut deck = |player: usize| -> Result<VecDeque<usize>> {
+        let block = blocks
+            .next()
+            .unwrap_or_else(|| Err(Error::parse(format!("Missing player {}", player))))?;
+        block.expect_header(&format!("Player {}:", player))?;
+
+        let mut deck = VecDeque::new();
+
+        for (n, line) in block.body().lines() {
+            let card = line.parse().at(n, 1)?;
+
+            // Ties are not part of the rules
+            if !cards.insert(card) {
+                return Err(Error::parse(format!("Duplicate card {}", card)).on_line(n));
             }
+
+            deck.push_back(card);
         }
-    }
 
-    (player_1, player_2)
+        Ok(deck)
+    };
+
+    Ok((deck(1)?, deck(2)?))
 }
 
-fn winner_score((player_1, player_2): Input) -> usize {
+/// Score of the player who still has cards
+pub fn winner_score((player_1, player_2): Input) -> usize {
     let winner = if player_1.is_empty() {
         player_2
     } else {
@@ -58,74 +88,174 @@ fn winner_score((player_1, player_2): Input) -> usize {
         .sum()
 }
 
-fn part_1((mut player_1, mut player_2): Input) -> usize {
-    while !(player_1.is_empty() || player_2.is_empty()) {
-        let p1 = player_1.pop_front().unwrap();
-        let p2 = player_2.pop_front().unwrap();
-
-        if p1 > p2 {
-            player_1.push_back(p1);
-            player_1.push_back(p2);
-        } else {
-            player_2.push_back(p2);
-            player_2.push_back(p1);
-        }
-    }
-
-    winner_score((player_1, player_2))
-}
-
 fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
     t.hash(&mut s);
     s.finish()
 }
 
-fn game(player_1: &mut VecDeque<usize>, player_2: &mut VecDeque<usize>) -> bool {
-    let mut seen_p1 = HashSet::new();
-    let mut seen_p2 = HashSet::new();
+/// Game of Combat, or of Recursive Combat, played round by round
+#[derive(Debug, Clone)]
+pub struct Game {
+    player_1: VecDeque<usize>,
+    player_2: VecDeque<usize>,
+    recursive: bool,
+    // Hashes of the hands played so far, only kept in Recursive Combat
+    seen_p1: HashSet<u64>,
+    seen_p2: HashSet<u64>,
+    /// Whether the game ended on a hand played before, which player 1 wins
+    repeated: bool,
+}
 
-    while !(player_1.is_empty() || player_2.is_empty()) {
-        // Check if a player has already had the same hand this round (relies on no hash collisions)
-        if !seen_p1.insert(calculate_hash(player_1)) || !seen_p2.insert(calculate_hash(player_2)) {
-            return true; // player 1 wins
+impl Game {
+    pub fn new((player_1, player_2): Input, recursive: bool) -> Self {
+        Self {
+            player_1,
+            player_2,
+            recursive,
+            seen_p1: HashSet::new(),
+            seen_p2: HashSet::new(),
+            repeated: false,
         }
+    }
 
-        let p1 = player_1.pop_front().unwrap();
-        let p2 = player_2.pop_front().unwrap();
+    pub fn decks(&self) -> (&VecDeque<usize>, &VecDeque<usize>) {
+        (&self.player_1, &self.player_2)
+    }
+
+    pub fn into_decks(self) -> Input {
+        (self.player_1, self.player_2)
+    }
 
-        let sub_game = p1 <= player_1.len() && p2 <= player_2.len();
+    pub fn is_over(&self) -> bool {
+        self.repeated || self.player_1.is_empty() || self.player_2.is_empty()
+    }
+
+    /// Player who won (1 or 2), once the game is over
+    pub fn winner(&self) -> Option<u8> {
+        if self.repeated || self.player_2.is_empty() {
+            Some(1)
+        } else if self.player_1.is_empty() {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    /// Play a round (with its sub-games), returns false instead when the game
+    /// is over
+    pub fn round(&mut self) -> bool {
+        if self.is_over() {
+            return false;
+        }
+
+        // Check if a player has already had the same hand this game (relies on no hash collisions)
+        if self.recursive
+            && (!self.seen_p1.insert(calculate_hash(&self.player_1))
+                || !self.seen_p2.insert(calculate_hash(&self.player_2)))
+        {
+            self.repeated = true;
+            return false;
+        }
+
+        let p1 = self.player_1.pop_front().unwrap();
+        let p2 = self.player_2.pop_front().unwrap();
+
+        let sub_game = self.recursive && p1 <= self.player_1.len() && p2 <= self.player_2.len();
 
         let p1_winner = if sub_game {
-            game(
-                &mut player_1.iter().take(p1).copied().collect(),
-                &mut player_2.iter().take(p2).copied().collect(),
-            )
+            let player_1 = self.player_1.iter().take(p1).copied().collect();
+            let player_2 = self.player_2.iter().take(p2).copied().collect();
+
+            Game::new((player_1, player_2), true).play()
         } else {
             p1 > p2
         };
 
         if p1_winner {
-            player_1.push_back(p1);
-            player_1.push_back(p2);
+            self.player_1.push_back(p1);
+            self.player_1.push_back(p2);
         } else {
-            player_2.push_back(p2);
-            player_2.push_back(p1);
+            self.player_2.push_back(p2);
+            self.player_2.push_back(p1);
         }
+
+        true
     }
 
-    player_1.len() > player_2.len()
+    /// Play until the game is over, returns whether player 1 won
+    pub fn play(&mut self) -> bool {
+        while self.round() {}
+
+        self.winner() == Some(1)
+    }
 }
 
-fn part_2((mut player_1, mut player_2): Input) -> usize {
-    let _ = game(&mut player_1, &mut player_2);
+pub fn part_1(input: Input) -> usize {
+    let mut game = Game::new(input, false);
+    game.play();
 
-    winner_score((player_1, player_2))
+    winner_score(game.into_decks())
+}
+
+pub fn part_2(input: Input) -> usize {
+    let mut game = Game::new(input, true);
+    game.play();
+
+    winner_score(game.into_decks())
+}
+
+/// Decks of `size` cards in total, dealt so that a game of Combat ends
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(2) / 2 * 2;
+    let mut cards: Vec<usize> = (1..=size).collect();
+
+    loop {
+        rng.shuffle(&mut cards);
+
+        let player_1: VecDeque<usize> = cards[..size / 2].iter().copied().collect();
+        let player_2: VecDeque<usize> = cards[size / 2..].iter().copied().collect();
+
+        if combat_ends(player_1, player_2) {
+            break;
+        }
+    }
+
+    let deck = |cards: &[usize]| -> String { cards.iter().map(|c| format!("{}\n", c)).collect() };
+
+    format!(
+        "Player 1:\n{}\nPlayer 2:\n{}",
+        deck(&cards[..size / 2]),
+        deck(&cards[size / 2..])
+    )
+}
+
+/// Whether a game of (non recursive) Combat ends, rather than looping forever
+fn combat_ends(mut player_1: VecDeque<usize>, mut player_2: VecDeque<usize>) -> bool {
+    let mut seen = HashSet::new();
+
+    while !(player_1.is_empty() || player_2.is_empty()) {
+        if !seen.insert((player_1.clone(), player_2.clone())) {
+            return false;
+        }
+
+        let p1 = player_1.pop_front().unwrap();
+        let p2 = player_2.pop_front().unwrap();
+
+        if p1 > p2 {
+            player_1.extend([p1, p2].iter());
+        } else {
+            player_2.extend([p2, p1].iter());
+        }
+    }
+
+    true
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "Player 1:
 9
@@ -144,13 +274,28 @@ Player 2:
 
     #[test]
     fn part_1_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(input), 306);
     }
 
+    #[test]
+    fn rounds() {
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        let mut game = Game::new(input, false);
+
+        assert!(game.round());
+        let (player_1, player_2) = game.decks();
+        assert_eq!(player_1, &[2, 6, 3, 1, 9, 5]);
+        assert_eq!(player_2, &[8, 4, 7, 10]);
+
+        assert!(!game.play());
+        assert_eq!(game.winner(), Some(2));
+        assert!(!game.round());
+    }
+
     #[test]
     fn part_2_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_2(input), 291);
     }
 }
@@ -1,25 +1,73 @@
-use std::io;
+//! Day 23: Crab Cups
+
 use std::io::prelude::*;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    match part {
-        1 => println!("{}", part_1(parse(input), 100)),
-        2 => println!("{}", part_2(parse(input))),
-        _ => unimplemented!(),
-    };
+use crate::parsing::single_line;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    const DAY: u8 = 23;
+
+    type Input = Vec<usize>;
+    type Part1 = String;
+    type Part2 = u64;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(cups: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(&cups, MOVES))
+    }
 
-    Ok(())
+    fn part_2(cups: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(&cups))
+    }
 }
 
-fn parse<const N: usize>(mut input: impl BufRead) -> Circle<N> {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
-    input_str.pop(); // Remove newline
-    Circle::parse(&input_str)
+impl Generate for Day23 {
+    const INPUT_SIZE: usize = 9;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+}
+
+/// Number of cups in the input
+pub const CUP_COUNT: usize = 9;
+
+/// Moves of part 1
+pub const MOVES: usize = 100;
+
+pub fn parse(input: impl BufRead) -> Result<Vec<usize>> {
+    let mut cups = Vec::new();
+
+    for (i, c) in single_line(input)?.chars().enumerate() {
+        let cup = match c.to_digit(10) {
+            Some(cup) if cup > 0 => cup as usize,
+            _ => return Err(Error::parse(format!("Unexpected label '{}'", c)).at(1, i + 1)),
+        };
+
+        if cups.contains(&cup) {
+            return Err(Error::parse(format!("Duplicate label {}", cup)).at(1, i + 1));
+        }
+
+        cups.push(cup);
+    }
+
+    if cups.len() != CUP_COUNT {
+        return Err(Error::parse(format!("Expected {} cups", CUP_COUNT)).on_line(1));
+    }
+
+    Ok(cups)
 }
 
+/// Circle of `N` cups, labelled from 1 to `N`
 #[derive(Debug, Clone)]
-struct Circle<const N: usize> {
+pub struct Circle<const N: usize> {
     nexts: Box<[usize]>,
     current_val: usize,
     min: usize,
@@ -27,7 +75,8 @@ struct Circle<const N: usize> {
 }
 
 impl<const N: usize> Circle<N> {
-    fn parse(input: &str) -> Self {
+    /// Circle starting with `cups`, followed by the missing labels up to `N`
+    pub fn new(cups: &[usize]) -> Self {
         let nexts = vec![0; N + 1]; // 1 value per index + 1 for 0/null
         let mut nexts = nexts.into_boxed_slice();
 
@@ -37,9 +86,7 @@ impl<const N: usize> Circle<N> {
         let mut first_value = 0;
         let mut prev_value = 0;
 
-        for (i, x) in input.chars().map(|c| c.to_digit(10).unwrap()).enumerate() {
-            let x = x as usize;
-
+        for (i, &x) in cups.iter().enumerate() {
             if i == 0 {
                 first_value = x;
             } else {
@@ -67,7 +114,7 @@ impl<const N: usize> Circle<N> {
         }
     }
 
-    fn step(&mut self) {
+    pub fn step(&mut self) {
         // Pickup 3 nexts
         let mut pickup = Vec::new();
 
@@ -105,14 +152,26 @@ impl<const N: usize> Circle<N> {
         self.current_val = after_pickup;
     }
 
-    fn run_steps(&mut self, moves: usize) {
+    /// Labels of the cups clockwise, from the current one
+    pub fn cups(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut cup = self.current_val;
+
+        (0..self.nexts.len() - 1).map(move |_| {
+            let label = cup;
+            cup = self.nexts[cup];
+            label
+        })
+    }
+
+    pub fn run_steps(&mut self, moves: usize) {
         for _ in 0..moves {
             self.step();
         }
     }
 }
 
-fn part_1(mut circle: Circle<9>, moves: usize) -> String {
+pub fn part_1(cups: &[usize], moves: usize) -> String {
+    let mut circle = Circle::<CUP_COUNT>::new(cups);
     circle.run_steps(moves);
 
     let mut out = String::new();
@@ -130,7 +189,8 @@ fn part_1(mut circle: Circle<9>, moves: usize) -> String {
     }
 }
 
-fn part_2(mut circle: Circle<1_000_000>) -> u64 {
+pub fn part_2(cups: &[usize]) -> u64 {
+    let mut circle = Circle::<1_000_000>::new(cups);
     circle.run_steps(10_000_000);
 
     let a = circle.nexts[1];
@@ -139,22 +199,32 @@ fn part_2(mut circle: Circle<1_000_000>) -> u64 {
     (a as u64) * (b as u64)
 }
 
+/// Labels of the cups in a random order, there are always `CUP_COUNT` of them
+/// whatever the `size`
+pub fn generate(rng: &mut Rng, _size: usize) -> String {
+    let mut cups: Vec<String> = (1..=CUP_COUNT).map(|cup| cup.to_string()).collect();
+    rng.shuffle(&mut cups);
+
+    cups.concat() + "\n"
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "389125467\n";
 
     #[test]
     fn part_1_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(&part_1(input.clone(), 10), "92658374");
-        assert_eq!(&part_1(input, 100), "67384529");
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(&part_1(&input, 10), "92658374");
+        assert_eq!(&part_1(&input, 100), "67384529");
     }
 
     #[test]
     fn part_2_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
-        assert_eq!(part_2(input), 149245887792);
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
+        assert_eq!(part_2(&input), 149245887792);
     }
 }
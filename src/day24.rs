@@ -1,22 +1,47 @@
-use std::io;
+//! Day 24: Lobby Layout
+
 use std::io::prelude::*;
 
 use std::collections::{HashMap, HashSet};
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use serde::{Deserialize, Serialize};
+
+use crate::parsing::records;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    const DAY: u8 = 24;
+
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(input))
+    }
 
-    match part {
-        1 => println!("{}", part_1(input)),
-        2 => println!("{}", part_2(input)),
-        _ => unimplemented!(),
-    };
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(input))
+    }
+}
 
-    Ok(())
+impl Generate for Day24 {
+    const INPUT_SIZE: usize = 500;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Dir {
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Dir {
     East,
     SouthEast,
     SouthWest,
@@ -25,57 +50,58 @@ enum Dir {
     NorthEast,
 }
 
-type Input = Vec<Vec<Dir>>;
-
-fn parse(mut input: impl BufRead) -> Input {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+pub type Input = Vec<Vec<Dir>>;
 
-    let mut all_dirs = Vec::new();
+fn parse_dirs(line: &str) -> Result<Vec<Dir>> {
     let mut dirs = Vec::new();
-
-    for line in input_str.lines() {
-        let mut chars = line.chars();
-        while let Some(c) = chars.next() {
-            match c {
-                'e' => dirs.push(Dir::East),
-                'w' => dirs.push(Dir::West),
-                's' => match chars.next() {
-                    Some('e') => dirs.push(Dir::SouthEast),
-                    Some('w') => dirs.push(Dir::SouthWest),
-                    _ => panic!("Failed to parse South_"),
-                },
-                'n' => match chars.next() {
-                    Some('e') => dirs.push(Dir::NorthEast),
-                    Some('w') => dirs.push(Dir::NorthWest),
-                    _ => panic!("Failed to parse North_"),
-                },
-                _ => panic!("Cannot parse {}", c),
+    let mut chars = line.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        // North and south are always followed by east or west
+        let next = if matches!(c, 's' | 'n') {
+            chars.next()
+        } else {
+            None
+        };
+
+        let dir = match (c, next) {
+            ('e', _) => Dir::East,
+            ('w', _) => Dir::West,
+            ('s', Some((_, 'e'))) => Dir::SouthEast,
+            ('s', Some((_, 'w'))) => Dir::SouthWest,
+            ('n', Some((_, 'e'))) => Dir::NorthEast,
+            ('n', Some((_, 'w'))) => Dir::NorthWest,
+            ('s', _) | ('n', _) => {
+                return Err(Error::parse(format!("Expected '{}e' or '{}w'", c, c)).at_column(i + 1))
             }
-        }
+            _ => return Err(Error::parse(format!("Unexpected direction '{}'", c)).at_column(i + 1)),
+        };
 
-        let mut new_dirs = Vec::new();
-        std::mem::swap(&mut new_dirs, &mut dirs);
-        all_dirs.push(new_dirs);
+        dirs.push(dir);
     }
 
-    all_dirs
+    Ok(dirs)
+}
+
+pub fn parse(input: impl BufRead) -> Result<Input> {
+    records(input, parse_dirs).collect()
 }
 
 // Using Cube coordinates: https://www.redblobgames.com/grids/hexagons/#coordinates-cube
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-struct TileIndex {
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct TileIndex {
     x: isize,
     y: isize,
     z: isize,
 }
 
 impl TileIndex {
-    fn new() -> Self {
+    /// Reference tile, in the center of the room
+    pub fn new() -> Self {
         Self { x: 0, y: 0, z: 0 }
     }
 
-    fn move_dir(&mut self, dir: Dir) {
+    pub fn move_dir(&mut self, dir: Dir) {
         match dir {
             Dir::East => {
                 self.x += 1;
@@ -105,10 +131,13 @@ impl TileIndex {
     }
 }
 
-struct Grid(HashSet<TileIndex>);
+/// Floor of hexagonal tiles, where only the black ones are stored
+#[derive(Debug, Clone)]
+pub struct Grid(HashSet<TileIndex>);
 
 impl Grid {
-    fn from_dirs(input: Input) -> Self {
+    /// Flip the tiles found by following each list of directions
+    pub fn from_dirs(input: Input) -> Self {
         let mut black_tiles = HashSet::new();
 
         for instr in input {
@@ -128,7 +157,7 @@ impl Grid {
         Self(black_tiles)
     }
 
-    fn step(&self) -> Self {
+    pub fn step(&self) -> Self {
         let mut neighbours = HashMap::<TileIndex, (bool, usize)>::new();
 
         for &idx in self.0.iter() {
@@ -154,27 +183,61 @@ impl Grid {
         )
     }
 
-    fn run_steps(&mut self, n: usize) {
+    pub fn run_steps(&mut self, n: usize) {
+        self.run_steps_with(n, |_| ())
+    }
+
+    /// `run_steps`, calling `on_step` with the initial floor and each day
+    pub fn run_steps_with(&mut self, n: usize, mut on_step: impl FnMut(&Self)) {
+        on_step(self);
+
         for _ in 0..n {
             *self = self.step();
+            on_step(self);
         }
     }
+
+    /// `(x, y)` pixels of the black tiles, drawn 2 pixels wide with every other
+    /// row shifted by one pixel (north is up, every tile has 6 neighbours)
+    pub fn pixels(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        self.0.iter().flat_map(|tile| {
+            let x = 2 * tile.x + tile.z;
+            vec![(x, tile.z), (x + 1, tile.z)]
+        })
+    }
+
+    pub fn black_count(&self) -> usize {
+        self.0.len()
+    }
 }
 
-fn part_1(input: Input) -> usize {
-    let black_tiles = Grid::from_dirs(input).0;
-    black_tiles.len()
+pub fn part_1(input: Input) -> usize {
+    Grid::from_dirs(input).black_count()
 }
 
-fn part_2(input: Input) -> usize {
+pub fn part_2(input: Input) -> usize {
     let mut grid = Grid::from_dirs(input);
     grid.run_steps(100);
-    grid.0.len()
+    grid.black_count()
+}
+
+/// `size` paths of 15 to 25 steps
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let mut path: String = (0..rng.range(15..26))
+                .map(|_| *rng.choose(&["e", "se", "sw", "w", "nw", "ne"]))
+                .collect();
+            path.push('\n');
+            path
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "sesenwnenenewseeswwswswwnenewsewsw
 neeenesenwnwwswnenewnwwsewnenwseswesw
@@ -199,13 +262,13 @@ wseweeenwnesenwwwswnew";
 
     #[test]
     fn part_1_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(input), 10);
     }
 
     #[test]
     fn part_2_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_2(input), 2208);
     }
 }
@@ -1,34 +1,73 @@
-use std::io;
+//! Day 25: Combo Breaker
+
 use std::io::prelude::*;
 
-pub fn solve(input: impl BufRead, part: u8) -> io::Result<()> {
-    let input = parse(input);
+use serde::{Deserialize, Serialize};
 
-    match part {
-        1 => println!("{}", part_1(input)),
-        2 => println!("{}", part_2(input)),
-        _ => unimplemented!(),
-    };
+use crate::error::Context;
+use crate::rng::Rng;
+use crate::{Error, Generate, Result, Solution};
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    const DAY: u8 = 25;
+
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input> {
+        parse(input)
+    }
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1> {
+        Ok(part_1(input))
+    }
+
+    fn part_2(input: Self::Input) -> Result<Self::Part2> {
+        Ok(part_2(input))
+    }
+}
 
-    Ok(())
+impl Generate for Day25 {
+    const INPUT_SIZE: usize = 10000000;
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
 }
 
-#[derive(Debug)]
-struct Input {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Input {
     card_public_key: usize,
     door_public_key: usize,
 }
 
-fn parse(mut input: impl BufRead) -> Input {
-    let mut input_str = String::new();
-    input.read_to_string(&mut input_str).unwrap();
+pub const MODULUS: usize = 20201227;
+
+fn parse_key(line: Option<std::io::Result<String>>, line_number: usize) -> Result<usize> {
+    let line = line.ok_or_else(|| Error::parse("Missing public key").on_line(line_number))??;
 
-    let (card, door) = input_str.trim_end().split_once('\n').unwrap();
+    let key = line.parse().at(line_number, 1)?;
 
-    Input {
-        card_public_key: card.parse().unwrap(),
-        door_public_key: door.parse().unwrap(),
+    if !(1..MODULUS).contains(&key) {
+        return Err(
+            Error::parse(format!("Public keys are between 1 and {}", MODULUS - 1))
+                .on_line(line_number),
+        );
     }
+
+    Ok(key)
+}
+
+pub fn parse(input: impl BufRead) -> Result<Input> {
+    let mut lines = input.lines();
+
+    Ok(Input {
+        card_public_key: parse_key(lines.next(), 1)?,
+        door_public_key: parse_key(lines.next(), 2)?,
+    })
 }
 
 fn find_loop_size(subject: usize, modulus: usize, public_key: usize) -> usize {
@@ -60,19 +99,38 @@ fn find_key(subject: usize, modulus: usize, mut loop_size: usize) -> usize {
     }
 }
 
-fn part_1(input: Input) -> usize {
-    let modulus = 20201227;
-    let door_loop_size = find_loop_size(7, modulus, input.door_public_key);
-    find_key(input.card_public_key, modulus, door_loop_size)
+pub fn part_1(input: Input) -> usize {
+    let door_loop_size = find_loop_size(7, MODULUS, input.door_public_key);
+    find_key(input.card_public_key, MODULUS, door_loop_size)
+}
+
+pub fn part_2(_input: Input) -> usize {
+    // There is no puzzle on the last day, its second star is given for all the others
+    0
 }
 
-fn part_2(input: Input) -> usize {
-    todo!()
+/// Public keys of the card and the door, with distinct loop sizes below `size`
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let max = size.clamp(3, MODULUS - 1) as u64;
+    let card = rng.range(1..max) as usize;
+    let door = loop {
+        let door = rng.range(1..max) as usize;
+        if door != card {
+            break door;
+        }
+    };
+
+    format!(
+        "{}\n{}\n",
+        find_key(7, MODULUS, card),
+        find_key(7, MODULUS, door)
+    )
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::io;
 
     const EXAMPLE: &str = "5764801\n17807724\n";
 
@@ -88,13 +146,13 @@ mod test {
 
     #[test]
     fn part_1_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_1(input), 14897079);
     }
 
     #[test]
     fn part_2_example() {
-        let input = parse(io::Cursor::new(EXAMPLE));
+        let input = parse(io::Cursor::new(EXAMPLE)).unwrap();
         assert_eq!(part_2(input), 0);
     }
 }
@@ -0,0 +1,182 @@
+use std::fmt;
+use std::io;
+use std::num::ParseIntError;
+use std::path::PathBuf;
+use std::str::Utf8Error;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error while reading, parsing or solving a puzzle input
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A file could not be opened
+    Open {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// Malformed input, with the position (starting at 1) of the issue when known
+    Parse {
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// The input is well formed, but there is no answer for it
+    NoSolution,
+}
+
+impl Error {
+    pub fn parse(message: impl Into<String>) -> Self {
+        Error::Parse {
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    /// Locate a parse error on a line, unless it already is
+    pub fn on_line(mut self, n: usize) -> Self {
+        if let Error::Parse { line, .. } = &mut self {
+            line.get_or_insert(n);
+        }
+
+        self
+    }
+
+    /// Locate a parse error at a column, unless it already is
+    pub fn at_column(mut self, n: usize) -> Self {
+        if let Error::Parse { column, .. } = &mut self {
+            column.get_or_insert(n);
+        }
+
+        self
+    }
+
+    pub fn at(self, line: usize, column: usize) -> Self {
+        self.on_line(line).at_column(column)
+    }
+
+    /// Whether this is caused by a missing file
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::Io(e) | Error::Open { source: e, .. } => e.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Open { path, source } => write!(f, "Cannot open {}: {}", path.display(), source),
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => {
+                match (line, column) {
+                    (Some(line), Some(column)) => write!(f, "line {}, column {}: ", line, column)?,
+                    (Some(line), None) => write!(f, "line {}: ", line)?,
+                    (None, Some(column)) => write!(f, "column {}: ", column)?,
+                    (None, None) => (),
+                }
+
+                f.write_str(message)
+            }
+            Error::NoSolution => f.write_str("No solution for this input"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) | Error::Open { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(error: ParseIntError) -> Self {
+        Error::parse(error.to_string())
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(error: Utf8Error) -> Self {
+        Error::parse(error.to_string())
+    }
+}
+
+/// Locate the errors of a `Result`
+pub trait Context<T> {
+    fn on_line(self, line: usize) -> Result<T>;
+
+    fn at_column(self, column: usize) -> Result<T>;
+
+    fn at(self, line: usize, column: usize) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for Result<T, E> {
+    fn on_line(self, line: usize) -> Result<T> {
+        self.map_err(|e| e.into().on_line(line))
+    }
+
+    fn at_column(self, column: usize) -> Result<T> {
+        self.map_err(|e| e.into().at_column(column))
+    }
+
+    fn at(self, line: usize, column: usize) -> Result<T> {
+        self.map_err(|e| e.into().on_line(line).at_column(column))
+    }
+}
+
+/// Column (starting at 1) of `part` in `line`, `part` has to be a slice of `line`
+pub fn column(line: &str, part: &str) -> usize {
+    let offset = part.as_ptr() as usize - line.as_ptr() as usize;
+    debug_assert!(offset <= line.len(), "Not a slice of the line");
+
+    offset + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let error = Error::parse("Unexpected character 'x'")
+            .at_column(4)
+            .on_line(12);
+        assert_eq!(
+            error.to_string(),
+            "line 12, column 4: Unexpected character 'x'"
+        );
+
+        let error = "12a".parse::<usize>().on_line(3).unwrap_err();
+        assert_eq!(error.to_string(), "line 3: invalid digit found in string");
+    }
+
+    #[test]
+    fn innermost_position_is_kept() {
+        let error = Error::parse("Oops").at(2, 5).on_line(1).at_column(1);
+        assert_eq!(error.to_string(), "line 2, column 5: Oops");
+    }
+
+    #[test]
+    fn column_of_slice() {
+        let line = "1-3 a: abcde";
+        let (rule, password) = line.split_once(": ").unwrap();
+
+        assert_eq!(column(line, rule), 1);
+        assert_eq!(column(line, password), 8);
+    }
+}
@@ -0,0 +1,416 @@
+//! Rectangular grids of cells, as found in many puzzle inputs
+
+use std::fmt;
+use std::io::prelude::*;
+use std::ops::{Index, IndexMut};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Context;
+use crate::parsing::lines;
+use crate::{Error, Result};
+
+/// Position of a cell, as `(x, y)` from the top left corner
+pub type Pos = (usize, usize);
+
+/// Direction of a move, as `(dx, dy)` with `y` going down
+pub type Dir = (isize, isize);
+
+/// Directions to the 4 orthogonal neighbours: N, W, S, E
+pub const DIRECTIONS_4: [Dir; 4] = [(0, -1), (-1, 0), (0, 1), (1, 0)];
+
+/// Directions to the 8 neighbours, including the diagonals
+pub const DIRECTIONS_8: [Dir; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Cell read from (and written as) a single character of a map
+pub trait Cell: Sized {
+    fn from_byte(byte: u8) -> Option<Self>;
+
+    fn to_char(&self) -> char;
+}
+
+/// `#` for set cells and `.` for the others
+impl Cell for bool {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            b'#' => Some(true),
+            b'.' => Some(false),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        if *self {
+            '#'
+        } else {
+            '.'
+        }
+    }
+}
+
+/// Row of a character map
+pub fn parse_row<T: Cell>(line: &str) -> Result<Vec<T>> {
+    line.bytes()
+        .enumerate()
+        .map(|(i, b)| {
+            T::from_byte(b).ok_or_else(|| {
+                Error::parse(format!("Unexpected character {:?}", b as char)).at_column(i + 1)
+            })
+        })
+        .collect()
+}
+
+/// Rectangular grid, stored row by row in a single buffer
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+        }
+    }
+}
+
+impl<T: Cell> Grid<T> {
+    /// Character map with one row per line
+    pub fn parse(input: impl BufRead) -> Result<Self> {
+        let mut grid = Self::default();
+
+        for line in lines(input) {
+            let (n, line) = line?;
+            grid.push_row(parse_row(&line).on_line(n)?).on_line(n)?;
+        }
+
+        Ok(grid)
+    }
+}
+
+impl<T> Grid<T> {
+    /// Grid from its cells, row by row. Without any cells, it is the empty
+    /// grid of 0 by 0 cells whatever the width.
+    pub fn new(width: usize, cells: Vec<T>) -> Self {
+        if cells.is_empty() {
+            return Self::default();
+        }
+
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "Cells don't fit in rows of {}",
+            width
+        );
+
+        Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    /// Append a row at the bottom, which has to be as wide as the others
+    pub fn push_row(&mut self, row: impl IntoIterator<Item = T>) -> Result<()> {
+        let len = self.cells.len();
+        self.cells.extend(row);
+        let row_width = self.cells.len() - len;
+
+        if row_width == 0 {
+            return Err(Error::parse("Empty row"));
+        }
+
+        if self.height == 0 {
+            self.width = row_width;
+        } else if row_width != self.width {
+            self.cells.truncate(len);
+            return Err(Error::parse(format!(
+                "Expected rows of {} cells",
+                self.width
+            )));
+        }
+
+        self.height += 1;
+
+        Ok(())
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (x, y): Pos) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, (x, y): Pos) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// All the cells, row by row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> {
+        // `chunks` panics on empty chunks, even without any cells
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Cells of a column, from top to bottom
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        assert!(x < self.width, "Column {} out of bounds", x);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// Positions of all the cells, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// Cells with their position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// Grid of the same size, with each cell computed from the cell at its position
+    pub fn map<U>(&self, mut f: impl FnMut(Pos, &T) -> U) -> Grid<U> {
+        let mut cells = Vec::with_capacity(self.cells.len());
+
+        for (y, row) in self.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                cells.push(f((x, y), cell));
+            }
+        }
+
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells,
+        }
+    }
+
+    /// Position of the neighbour in a direction, if it is in the grid
+    pub fn offset(&self, (x, y): Pos, (dx, dy): Dir) -> Option<Pos> {
+        // Wrapping below 0 also ends up out of the grid
+        let x = x.wrapping_add_signed(dx);
+        let y = y.wrapping_add_signed(dy);
+
+        if x < self.width && y < self.height {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Positions of the orthogonal neighbours in the grid
+    pub fn neighbours_4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DIRECTIONS_4
+            .iter()
+            .filter_map(move |&dir| self.offset(pos, dir))
+    }
+
+    /// Positions of the neighbours in the grid, including the diagonal ones
+    pub fn neighbours_8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DIRECTIONS_8
+            .iter()
+            .filter_map(move |&dir| self.offset(pos, dir))
+    }
+
+    /// Positions seen from `pos` (excluded) looking in a direction, up to the
+    /// edge of the grid
+    pub fn ray(&self, pos: Pos, dir: Dir) -> impl Iterator<Item = Pos> + '_ {
+        std::iter::successors(self.offset(pos, dir), move |&pos| self.offset(pos, dir))
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// Grid of `width` by `height` cells, all set to `cell`
+    pub fn filled(width: usize, height: usize, cell: T) -> Self {
+        Self::new(width, vec![cell; width * height])
+    }
+
+    /// Rectangle of `width` by `height` cells, with its top left corner at `pos`
+    pub fn sub_grid(&self, (x, y): Pos, width: usize, height: usize) -> Self {
+        assert!(x + width <= self.width && y + height <= self.height);
+
+        let cells = self
+            .rows()
+            .skip(y)
+            .take(height)
+            .flat_map(|row| &row[x..x + width]);
+
+        Self::new(width, cells.cloned().collect())
+    }
+
+    /// Rotate 90deg anti-clockwise
+    pub fn rotate(&self) -> Self {
+        let cells = (0..self.width)
+            .rev()
+            .flat_map(|x| self.column(x))
+            .cloned()
+            .collect();
+
+        Self::new(self.height, cells)
+    }
+
+    /// Flip in the vertical direction (around the horizontal axis)
+    pub fn flip_v(&self) -> Self {
+        Self::new(self.width, self.rows().rev().flatten().cloned().collect())
+    }
+
+    /// Flip in the horizontal direction (around the vertical axis)
+    pub fn flip_h(&self) -> Self {
+        let cells = self.rows().flat_map(|row| row.iter().rev()).cloned();
+
+        Self::new(self.width, cells.collect())
+    }
+}
+
+/// Positions past the end of a row panic, rather than reading the next row
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos).expect("Position out of the grid")
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos).expect("Position out of the grid")
+    }
+}
+
+/// Character map, as parsed by `Grid::parse`
+impl<T: Cell> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell.to_char())?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "#..\n.#.\n##.\n.#.\n";
+
+    fn example() -> Grid<bool> {
+        Grid::parse(EXAMPLE.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let grid = example();
+
+        assert_eq!((grid.width(), grid.height()), (3, 4));
+        assert!(grid[(1, 2)]);
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = Grid::<bool>::parse("#.\n.x".as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 2: Unexpected character 'x'"
+        );
+
+        let error = Grid::<bool>::parse("#.\n.#.".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: Expected rows of 2 cells");
+
+        let error = Grid::<bool>::parse("#.\n\n.#\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: Empty row");
+
+        // Blank lines at the end aren't rows
+        assert_eq!(
+            Grid::<bool>::parse("#.\n\n".as_bytes()).unwrap().height(),
+            1
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = example();
+
+        assert_eq!(
+            grid.neighbours_4((0, 0)).collect::<Vec<_>>(),
+            [(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbours_8((2, 3)).count(), 3);
+        assert_eq!(grid.neighbours_8((1, 1)).count(), 8);
+        assert_eq!(
+            grid.ray((0, 3), (1, -1)).collect::<Vec<_>>(),
+            [(1, 2), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn transformations() {
+        let grid = example();
+
+        assert_eq!(grid.rotate().to_string(), "....\n.###\n#.#.\n");
+        assert_eq!(grid.rotate().rotate().rotate().rotate(), grid);
+        assert_eq!(grid.flip_v().to_string(), ".#.\n##.\n.#.\n#..\n");
+        assert_eq!(grid.flip_h().to_string(), "..#\n.#.\n.##\n.#.\n");
+        assert_eq!(grid.sub_grid((1, 1), 2, 2).to_string(), "#.\n#.\n");
+    }
+
+    #[test]
+    fn empty_grids() {
+        let empty = Grid::<bool>::default();
+
+        assert_eq!(Grid::filled(0, 3, false), empty);
+        assert_eq!(Grid::filled(3, 0, false), empty);
+        assert_eq!(Grid::<bool>::new(0, vec![]), empty);
+        assert_eq!(empty.rotate(), empty);
+        assert_eq!(empty.flip_v(), empty);
+        assert_eq!(empty.flip_h(), empty);
+        assert_eq!(example().sub_grid((1, 1), 0, 2), empty);
+        assert_eq!(empty.to_string(), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "Position out of the grid")]
+    fn index_past_the_end_of_a_row() {
+        let grid = example();
+        let _ = grid[(3, 0)];
+    }
+}
@@ -0,0 +1,134 @@
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use flate2::bufread::MultiGzDecoder;
+
+use crate::error::{Error, Result};
+
+/// Environment variable overriding the directory of the inputs
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+/// Environment variable selecting a named input set
+pub const USER_VAR: &str = "AOC_USER";
+
+/// Directory holding the puzzle inputs and their expected answers.
+///
+/// Several sets of inputs (from different accounts) can live side by side in
+/// sub directories named after their user, e.g. `inputs/<user>/day_NN.txt`.
+#[derive(Debug, Clone)]
+pub struct Inputs {
+    dir: PathBuf,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self::new("inputs")
+    }
+}
+
+impl Inputs {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Inputs from `$AOC_INPUT_DIR` (`inputs` when unset), in the `$AOC_USER` set if any
+    pub fn from_env() -> Self {
+        let inputs = env::var_os(INPUT_DIR_VAR).map_or_else(Self::default, Self::new);
+
+        match env::var(USER_VAR) {
+            Ok(user) => inputs.user(&user),
+            Err(_) => inputs,
+        }
+    }
+
+    /// Named input set of a user
+    pub fn user(&self, user: &str) -> Self {
+        Self::new(self.dir.join(user))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn day_path(&self, day: u8) -> PathBuf {
+        self.dir.join(format!("day_{:0>2}.txt", day))
+    }
+
+    pub fn answers_path(&self) -> PathBuf {
+        self.dir.join("answers.txt")
+    }
+
+    pub fn day(&self, day: u8) -> Result<Box<dyn BufRead>> {
+        open(self.day_path(day))
+    }
+
+    pub fn answers(&self) -> Result<Box<dyn BufRead>> {
+        open(self.answers_path())
+    }
+}
+
+/// First bytes of any gzip file
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Open a file for reading, decompressing it on the fly if it is gzipped
+pub fn open(path: impl Into<PathBuf>) -> Result<Box<dyn BufRead>> {
+    let path = path.into();
+    let with_path = |source| Error::Open {
+        path: path.clone(),
+        source,
+    };
+
+    let mut file = BufReader::new(File::open(&path).map_err(with_path)?);
+
+    if file.fill_buf().map_err(with_path)?.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        let inputs = Inputs::new("puzzles").user("alice");
+
+        assert_eq!(inputs.day_path(7), Path::new("puzzles/alice/day_07.txt"));
+        assert_eq!(
+            inputs.answers_path(),
+            Path::new("puzzles/alice/answers.txt")
+        );
+    }
+
+    #[test]
+    fn missing_file_names_its_path() {
+        let error = Inputs::new("no/such/dir").day(1).err().unwrap();
+
+        assert!(error.is_not_found());
+        assert!(error
+            .to_string()
+            .starts_with("Cannot open no/such/dir/day_01.txt: "));
+    }
+
+    #[test]
+    fn gzipped_file() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let path = env::temp_dir().join(format!("aoc2020-{}.txt.gz", std::process::id()));
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        encoder.write_all(b"1721\n979\n").unwrap();
+        encoder.finish().unwrap();
+
+        let mut content = String::new();
+        open(&path).unwrap().read_to_string(&mut content).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(content, "1721\n979\n");
+    }
+}
@@ -1,9 +1,167 @@
-use std::fs::File;
-use std::io;
-use std::io::BufReader;
+//! Solutions to the [Advent of Code 2020](https://adventofcode.com/2020) puzzles.
+//!
+//! Every day has its own module, with a `parse` function reading its puzzle
+//! input, the types it is parsed into, its `part_1` and `part_2` functions, and
+//! a `generate` function writing random puzzle inputs (from a seeded [`rng::Rng`]):
+//!
+//! ```
+//! use aoc2020::day01;
+//!
+//! let expenses = day01::parse("1721\n979\n366\n299\n675\n1456".as_bytes()).unwrap();
+//! assert_eq!(day01::part_1(&expenses), Some(514579));
+//! ```
+//!
+//! Each module also has a unit struct implementing [`Solution`] and
+//! [`Generate`], which is how the days are listed in [`DAYS`] and run
+//! generically.
 
-pub fn input_file(day: u8) -> io::Result<BufReader<File>> {
-    let input_path = format!("inputs/day_{:0>2}.txt", day);
+use std::io::BufRead;
 
-    Ok(BufReader::new(File::open(input_path)?))
+mod answer;
+mod cache;
+pub mod error;
+pub mod grid;
+mod inputs;
+pub mod parallel;
+pub mod parsing;
+pub mod render;
+pub mod repl;
+pub mod rng;
+mod solution;
+
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+
+pub use answer::{read_answers, Answer, Answers};
+pub use cache::{Cache, CACHE_DIR_VAR};
+pub use error::{Error, Result};
+pub use inputs::{open, Inputs, INPUT_DIR_VAR, USER_VAR};
+pub use solution::{Day, Generate, Solution};
+
+/// Solutions of every day, in order
+pub const DAYS: &[&dyn Day] = &[
+    &day01::Day01,
+    &day02::Day02,
+    &day03::Day03,
+    &day04::Day04,
+    &day05::Day05,
+    &day06::Day06,
+    &day07::Day07,
+    &day08::Day08,
+    &day09::Day09,
+    &day10::Day10,
+    &day11::Day11,
+    &day12::Day12,
+    &day13::Day13,
+    &day14::Day14,
+    &day15::Day15,
+    &day16::Day16,
+    &day17::Day17,
+    &day18::Day18,
+    &day19::Day19,
+    &day20::Day20,
+    &day21::Day21,
+    &day22::Day22,
+    &day23::Day23,
+    &day24::Day24,
+    &day25::Day25,
+];
+
+pub fn find_day(day: u8) -> Option<&'static dyn Day> {
+    DAYS.iter().copied().find(|d| d.day() == day)
+}
+
+/// Input of a day, from the inputs selected by the environment
+pub fn input_file(day: u8) -> Result<Box<dyn BufRead>> {
+    Inputs::from_env().day(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_inputs_are_solvable() {
+        for day in DAYS {
+            // These parts take millions of turns whatever the input
+            let parts = if [15, 23].contains(&day.day()) { 1 } else { 2 };
+
+            for seed in 0..3 {
+                let input = day.generate(seed, Some(10));
+                let parsed = day.parse(&mut input.as_bytes()).unwrap();
+
+                for part in 1..=parts {
+                    let answer = day.solve(&*parsed, part).unwrap();
+                    assert!(
+                        answer.is_ok(),
+                        "Day {} part {} with seed {}:\n{}",
+                        day.day(),
+                        part,
+                        seed,
+                        input
+                    );
+                }
+            }
+        }
+    }
+
+    /// Small random edits of generated inputs, which get further into the
+    /// parsers than random bytes (the fuzz targets in `fuzz/` go further still)
+    #[test]
+    fn parsers_reject_corrupted_inputs() {
+        let mut rng = rng::Rng::new(0);
+
+        for day in DAYS {
+            for seed in 0..50 {
+                let mut input = day.generate(seed, Some(5)).into_bytes();
+
+                for _ in 0..rng.range(1..4) {
+                    let i = rng.index(input.len() + 1);
+                    let byte = *rng.choose(b"0123456789 \n:-+#.,()|\"abcxyzLR\xff");
+
+                    match rng.range(0..4) {
+                        0 if i < input.len() => {
+                            input.remove(i);
+                        }
+                        1 if i < input.len() => input[i] = byte,
+                        2 => input.truncate(i),
+                        _ => input.insert(i, byte),
+                    }
+                }
+
+                // Errors are expected, only panics fail
+                let _ = day.parse(&mut &input[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_inputs_are_reproducible() {
+        for day in DAYS {
+            assert_eq!(day.generate(42, Some(10)), day.generate(42, Some(10)));
+        }
+    }
 }
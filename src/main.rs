@@ -1,89 +1,848 @@
-#![feature(test)]
-#![feature(try_blocks)]
-#![feature(str_split_once)]
-#![feature(half_open_range_patterns)]
-#![feature(exclusive_range_pattern)]
-#![feature(iterator_fold_self)]
-#![feature(min_const_generics)]
-
-extern crate test;
-
+use std::fmt;
+use std::fs::{self, File};
 use std::io;
 use std::io::prelude::*;
+use std::io::{BufWriter, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
+use aoc2020::{day01, day02, day03, parallel, render, Answer, Cache, Day, Inputs, Result, DAYS};
 
 #[derive(Debug, StructOpt)]
-#[structopt(name = "aoc2020", about = "Basile's Advent of Code 2020")]
+#[structopt(
+    name = "aoc2020",
+    about = "Basile's Advent of Code 2020",
+    setting = AppSettings::SubcommandsNegateReqs
+)]
 struct Opt {
-    day: u8,
-    part: u8,
-    #[structopt(long)]
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+    #[structopt(required_unless = "all")]
+    day: Option<u8>,
+    #[structopt(required_unless = "all")]
+    part: Option<u8>,
+    #[structopt(long, conflicts_with = "input")]
     stdin: bool,
+    /// Read the input from this file instead (which can be gzipped)
+    #[structopt(long, parse(from_os_str))]
+    input: Option<PathBuf>,
+    /// Directory of the inputs
+    #[structopt(long, env = aoc2020::INPUT_DIR_VAR, default_value = "inputs")]
+    input_dir: PathBuf,
+    /// Use the named input set of a user, from <input-dir>/<user>
+    #[structopt(long, env = aoc2020::USER_VAR)]
+    user: Option<String>,
+    /// Save the parsed inputs in this directory, and load them from there
+    /// instead of parsing them again
+    #[structopt(long, global = true, env = aoc2020::CACHE_DIR_VAR, parse(from_os_str))]
+    cache: Option<PathBuf>,
+    /// Run every part of every day, and time them
+    #[structopt(long, conflicts_with_all = &["day", "part", "stdin", "input"])]
+    all: bool,
+    /// Print the results as text or as JSON (one object per run, or an array of them)
+    #[structopt(long, global = true, default_value = "text", possible_values = &["text", "json"])]
+    format: Format,
+}
+
+/// How the results are printed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Unknown format {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Check the answers of every day against the expected ones in answers.txt
+    Verify {
+        /// Only check this day
+        day: Option<u8>,
+    },
+    /// Write a random puzzle input for a day to the standard output
+    Generate {
+        day: u8,
+        /// Size of the input, in records of the day (e.g. lines or tiles), a
+        /// typical size by default
+        #[structopt(long)]
+        size: Option<usize>,
+        /// Seed of the random generator, from the clock by default (the seed
+        /// is printed on the standard error)
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+    /// Play the generations of a simulation (days 11, 17 and 24) in the
+    /// terminal, or save them as images
+    Animate {
+        day: u8,
+        part: u8,
+        /// Save an animated GIF to this file instead
+        #[structopt(long, parse(from_os_str))]
+        gif: Option<PathBuf>,
+        /// Save a PPM image per generation in this directory instead
+        #[structopt(long, parse(from_os_str))]
+        ppm: Option<PathBuf>,
+        /// Size in pixels of the cells in the images
+        #[structopt(long, default_value = "4")]
+        scale: usize,
+        /// Time between generations, in milliseconds
+        #[structopt(long, default_value = "100")]
+        delay: u64,
+    },
+    /// List all the expenses of day 1 adding up to a goal
+    KSum {
+        /// Number of expenses to add up, at most 6
+        #[structopt(long, default_value = "2")]
+        k: usize,
+        /// Sum to find
+        #[structopt(long, default_value = "2020", allow_hyphen_values = true)]
+        goal: i64,
+        /// List each set of values once, with the number of ways to pick it
+        /// from the input, instead of every set of indices
+        #[structopt(long)]
+        distinct: bool,
+    },
+    /// Check the passwords of day 2 against a policy, like
+    /// "all(count, min-length 8, any(class digit, class symbol), forbid abc)",
+    /// listing the clauses each of them breaks
+    Policy {
+        policy: day02::Policy,
+        /// What positions, counts and lengths are in: byte (as in the
+        /// puzzle), char or grapheme (for non-ASCII passwords)
+        #[structopt(long, default_value = "byte")]
+        unit: day02::Unit,
+    },
+    /// Trace the path of the toboggan of day 3 down a slope
+    Path {
+        /// Squares to the right at each step, to the left when negative
+        #[structopt(long, default_value = "3", allow_hyphen_values = true)]
+        dx: isize,
+        /// Squares down at each step
+        #[structopt(long, default_value = "1")]
+        dy: usize,
+        /// Draw the forest with the path over it
+        #[structopt(long)]
+        draw: bool,
+    },
+    /// Find the slope of day 3 with the fewest trees, within bounds
+    Slopes {
+        /// Largest number of squares to the right or left at each step
+        #[structopt(long, default_value = "10")]
+        max_dx: isize,
+        /// Largest number of squares down at each step
+        #[structopt(long, default_value = "2")]
+        max_dy: usize,
+        /// Find the slope with the most trees instead
+        #[structopt(long)]
+        most: bool,
+    },
+    /// Explore the input of a day step by step (days 8, 11, 18, 22 and 23),
+    /// with commands read from the standard input
+    Repl {
+        day: u8,
+        /// Part whose rules are used (days 11 and 22)
+        #[structopt(default_value = "1")]
+        part: u8,
+    },
 }
 
-fn main() -> io::Result<()> {
+fn main() {
     let opt = Opt::from_args();
 
+    let mut inputs = Inputs::new(opt.input_dir);
+    if let Some(user) = &opt.user {
+        inputs = inputs.user(user);
+    }
+
+    let source = if opt.stdin {
+        Source::Stdin
+    } else if let Some(path) = opt.input {
+        Source::File(path)
+    } else {
+        Source::Inputs(inputs.clone())
+    };
+
+    let cache = opt.cache.map(Cache::new);
+
+    let result = match (opt.cmd, opt.all, opt.day, opt.part) {
+        (Some(Command::Verify { day }), _, _, _) => {
+            verify(&inputs, cache.as_ref(), day, opt.format)
+        }
+        (Some(Command::Generate { day, size, seed }), _, _, _) => generate(day, size, seed),
+        (
+            Some(Command::Animate {
+                day,
+                part,
+                gif,
+                ppm,
+                scale,
+                delay,
+            }),
+            _,
+            _,
+            _,
+        ) => animate(source, day, part, gif, ppm, scale, delay),
+        (Some(Command::Repl { day, part }), _, _, _) => repl(source, day, part),
+        (Some(Command::KSum { k, goal, distinct }), _, _, _) => {
+            k_sum(source, k, goal, distinct, opt.format)
+        }
+        (Some(Command::Policy { policy, unit }), _, _, _) => {
+            check_policy(source, &policy, unit, opt.format)
+        }
+        (Some(Command::Path { dx, dy, draw }), _, _, _) => path(source, dx, dy, draw, opt.format),
+        (
+            Some(Command::Slopes {
+                max_dx,
+                max_dy,
+                most,
+            }),
+            _,
+            _,
+            _,
+        ) => slopes(source, max_dx, max_dy, most, opt.format),
+        (None, true, _, _) => run_all(&inputs, cache.as_ref(), opt.format),
+        (None, false, Some(day), Some(part)) => run(source, cache.as_ref(), day, part, opt.format),
+        (None, false, _, _) => unreachable!("A day and a part are required without --all"),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn find_day(day: u8) -> Result<&'static dyn Day> {
+    let day = aoc2020::find_day(day);
+
+    Ok(day.ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "No solution for this day"))?)
+}
+
+/// Where to read the input of a single run from
+enum Source {
+    Inputs(Inputs),
+    File(PathBuf),
+    Stdin,
+}
+
+impl Source {
+    /// Input of a day, with the file it is read from (`None` for the standard input)
+    fn open(self, day: u8) -> Result<(Box<dyn BufRead>, Option<PathBuf>)> {
+        Ok(match self {
+            Source::Inputs(inputs) => (inputs.day(day)?, Some(inputs.day_path(day))),
+            Source::File(path) => (aoc2020::open(&path)?, Some(path)),
+            Source::Stdin => (Box::new(io::stdin().lock()), None),
+        })
+    }
+}
+
+fn run(source: Source, cache: Option<&Cache>, day: u8, part: u8, format: Format) -> Result<()> {
+    let day = find_day(day)?;
+
+    if !(1..=day.parts()).contains(&part) {
+        Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "No solution for this part",
+        ))?;
+    }
+
+    let (mut input, path) = source.open(day.day())?;
+
+    let runs = run_parts(day, &mut input, path.as_deref(), cache, part..=part)?;
+
+    match format {
+        Format::Text => println!("{}", runs[0].answer),
+        Format::Json => println!("{}", runs[0].to_json()),
+    }
+
+    Ok(())
+}
+
+/// Answer to one part, with the time it took to get it
+struct Run {
+    day: u8,
+    part: u8,
+    answer: Answer,
+    parse_time: Duration,
+    solve_time: Duration,
+    /// File the input was read from, `None` for the standard input
+    input: Option<PathBuf>,
+}
+
+impl Run {
+    /// Fields of the JSON object of the run, without the braces
+    fn json_fields(&self) -> String {
+        let input = match &self.input {
+            Some(path) => json_string(&path.to_string_lossy()),
+            None => "null".to_string(),
+        };
+
+        format!(
+            r#""day":{},"part":{},"answer":{},"parse_time":{},"solve_time":{},"input":{}"#,
+            self.day,
+            self.part,
+            // As a string, numbers can be bigger than what JSON parsers handle
+            json_string(&self.answer.to_string()),
+            self.parse_time.as_secs_f64(),
+            self.solve_time.as_secs_f64(),
+            input
+        )
+    }
+
+    fn to_json(&self) -> String {
+        format!("{{{}}}", self.json_fields())
+    }
+}
+
+/// Parse the input of a day once (or load it from the cache), then solve some
+/// of its parts
+fn run_parts(
+    day: &dyn Day,
+    input: &mut dyn BufRead,
+    path: Option<&Path>,
+    cache: Option<&Cache>,
+    parts: impl Iterator<Item = u8>,
+) -> Result<Vec<Run>> {
+    let start = Instant::now();
+    let parsed = match cache {
+        Some(cache) => day.parse_cached(input, cache)?,
+        None => day.parse(input)?,
+    };
+    let parse_time = start.elapsed();
+
+    parts
+        .map(|part| {
+            let start = Instant::now();
+            let answer = day
+                .solve(&*parsed, part)
+                .expect("Part counted by the day")?;
+            let solve_time = start.elapsed();
+
+            Ok(Run {
+                day: day.day(),
+                part,
+                answer,
+                parse_time,
+                solve_time,
+                input: path.map(Path::to_path_buf),
+            })
+        })
+        .collect()
+}
+
+/// Parse the input of a day once, then solve all its parts
+fn run_day(day: &dyn Day, inputs: &Inputs, cache: Option<&Cache>) -> Result<Vec<Run>> {
+    let path = inputs.day_path(day.day());
+    let mut input = aoc2020::open(&path)?;
+
+    run_parts(day, &mut input, Some(&path), cache, 1..=day.parts())
+}
+
+fn run_all(inputs: &Inputs, cache: Option<&Cache>, format: Format) -> Result<()> {
+    // With the `parallel` feature the days run at the same time, which makes
+    // each of them a bit slower
+    let days = parallel::map(DAYS, |day| run_day(*day, inputs, cache));
+
+    let mut runs = Vec::new();
+
+    for day in days {
+        runs.append(&mut day?);
+    }
+
+    match format {
+        Format::Text => print_table(&runs),
+        Format::Json => print_json_array(runs.iter().map(Run::to_json)),
+    }
+
+    Ok(())
+}
+
+/// JSON string literal of `s`
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+/// JSON array, with one object per line
+fn print_json_array(objects: impl Iterator<Item = String>) {
+    let objects: Vec<String> = objects.collect();
+
+    if objects.is_empty() {
+        println!("[]");
+    } else {
+        println!("[\n  {}\n]", objects.join(",\n  "));
+    }
+}
+
+fn print_table(runs: &[Run]) {
+    let answers: Vec<String> = runs.iter().map(|run| run.answer.to_string()).collect();
+    let width = answers.iter().map(|a| a.len()).max().unwrap_or(0).max(6);
+
+    println!(
+        "{:>3} {:>4}  {:<width$}  {:>10} {:>10}",
+        "Day",
+        "Part",
+        "Answer",
+        "Parse",
+        "Solve",
+        width = width
+    );
+
+    let mut total_parse = Duration::default();
+    let mut total_solve = Duration::default();
+
+    for (run, answer) in runs.iter().zip(answers) {
+        // The input is parsed once per day, and shared by all its parts
+        let parse_time = if run.part == 1 {
+            total_parse += run.parse_time;
+            format!("{:.2?}", run.parse_time)
+        } else {
+            String::new()
+        };
+        total_solve += run.solve_time;
+
+        println!(
+            "{:>3} {:>4}  {:<width$}  {:>10} {:>10.2?}",
+            run.day,
+            run.part,
+            answer,
+            parse_time,
+            run.solve_time,
+            width = width
+        );
+    }
+
+    println!(
+        "{:<width$}  {:>10.2?} {:>10.2?}",
+        "Total",
+        total_parse,
+        total_solve,
+        width = width + 10
+    );
+    println!(
+        "{:<width$}  {:>21.2?}",
+        "Total (parse + solve)",
+        total_parse + total_solve,
+        width = width + 10
+    );
+}
+
+/// Answer to a part compared to the expected one, `run` is `None` without input
+struct Check {
+    day: u8,
+    part: u8,
+    /// Run of the part, `Ok(None)` without an input and an error when the
+    /// day couldn't be run
+    run: Result<Option<Run>, String>,
+    expected: Option<Answer>,
+}
+
+impl Check {
+    fn status(&self) -> &'static str {
+        match (&self.run, &self.expected) {
+            (Err(_), _) => "FAIL",
+            (Ok(None), _) => "missing input",
+            (Ok(Some(_)), None) => "missing",
+            (Ok(Some(run)), Some(expected)) if run.answer == *expected => "pass",
+            (Ok(Some(_)), Some(_)) => "FAIL",
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let run = match &self.run {
+            Ok(Some(run)) => run.json_fields(),
+            Ok(None) => format!(r#""day":{},"part":{}"#, self.day, self.part),
+            Err(error) => format!(
+                r#""day":{},"part":{},"error":{}"#,
+                self.day,
+                self.part,
+                json_string(error)
+            ),
+        };
+        let expected = match &self.expected {
+            Some(answer) => json_string(&answer.to_string()),
+            None => "null".to_string(),
+        };
+
+        format!(
+            r#"{{{},"expected":{},"status":{}}}"#,
+            run,
+            expected,
+            json_string(&self.status().to_lowercase())
+        )
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>3} {:>4}  ", self.day, self.part)?;
+
+        match (&self.run, &self.expected) {
+            (Err(error), Some(expected)) => write!(
+                f,
+                "{:<7}  error: {} (expected {})",
+                self.status(),
+                error,
+                expected
+            ),
+            (Err(error), None) => write!(f, "{:<7}  error: {}", self.status(), error),
+            (Ok(None), _) => f.write_str(self.status()),
+            (Ok(Some(run)), Some(expected)) if run.answer != *expected => write!(
+                f,
+                "{:<7}  {} (expected {})",
+                self.status(),
+                run.answer,
+                expected
+            ),
+            (Ok(Some(run)), _) => write!(f, "{:<7}  {}", self.status(), run.answer),
+        }
+    }
+}
+
+fn verify(inputs: &Inputs, cache: Option<&Cache>, day: Option<u8>, format: Format) -> Result<()> {
+    let mut expected = aoc2020::read_answers(inputs.answers()?)?;
+
+    let days = match day {
+        Some(day) => vec![find_day(day)?],
+        None => DAYS.to_vec(),
+    };
+
+    let results = parallel::map(&days, |day| run_day(*day, inputs, cache));
+    let mut checks = Vec::new();
+
+    for (day, result) in days.into_iter().zip(results) {
+        // A day which can't be run fails all its parts, without hiding the others
+        let runs: Vec<Result<Option<Run>, String>> = match result {
+            Ok(runs) => runs.into_iter().map(|run| Ok(Some(run))).collect(),
+            Err(e) if e.is_not_found() => (1..=day.parts()).map(|_| Ok(None)).collect(),
+            Err(e) => (1..=day.parts()).map(|_| Err(e.to_string())).collect(),
+        };
+
+        for (part, run) in (1..).zip(runs) {
+            checks.push(Check {
+                day: day.day(),
+                part,
+                run,
+                expected: expected.remove(&(day.day(), part)),
+            });
+        }
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status() == status).count();
+    let failed = count("FAIL");
+
+    match format {
+        Format::Text => {
+            for check in &checks {
+                println!("{}", check);
+            }
+
+            println!(
+                "{} passed, {} failed, {} missing",
+                count("pass"),
+                failed,
+                count("missing") + count("missing input")
+            );
+        }
+        Format::Json => print_json_array(checks.iter().map(Check::to_json)),
+    }
+
+    if failed > 0 {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn generate(day: u8, size: Option<usize>, seed: Option<u64>) -> Result<()> {
+    let day = find_day(day)?;
+
+    let seed = seed.unwrap_or_else(|| {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        eprintln!("seed: {}", seed);
+        seed
+    });
+
+    print!("{}", day.generate(seed, size));
+
+    Ok(())
+}
+
+fn animate(
+    source: Source,
+    day: u8,
+    part: u8,
+    gif: Option<PathBuf>,
+    ppm: Option<PathBuf>,
+    scale: usize,
+    delay: u64,
+) -> Result<()> {
+    let (input, _) = source.open(day)?;
+    let frames = render::simulation(day, part, input)?;
+
+    if let Some(path) = &gif {
+        // GIF delays are in hundredths of a second
+        let delay = (delay / 10).min(u16::MAX as u64) as u16;
+        render::write_gif(&frames, scale, delay, BufWriter::new(File::create(path)?))?;
+    }
+
+    if let Some(dir) = &ppm {
+        fs::create_dir_all(dir)?;
+
+        for (i, frame) in frames.iter().enumerate() {
+            let file = File::create(dir.join(format!("frame_{:04}.ppm", i)))?;
+            render::write_ppm(frame, scale, BufWriter::new(file))?;
+        }
+    }
+
+    if gif.is_none() && ppm.is_none() {
+        // Clear the screen once, then draw each frame over the previous one
+        print!("\x1b[2J");
+
+        for (i, frame) in frames.iter().enumerate() {
+            println!(
+                "\x1b[H{}generation {}/{}",
+                render::ansi(frame),
+                i,
+                frames.len() - 1
+            );
+            io::stdout().flush()?;
+            thread::sleep(Duration::from_millis(delay));
+        }
+    }
+
+    Ok(())
+}
+
+fn repl(source: Source, day: u8, part: u8) -> Result<()> {
+    if let Source::Stdin = source {
+        Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The standard input is for the commands",
+        ))?;
+    }
+
+    let (input, _) = source.open(day)?;
     let stdin = io::stdin();
-    let input: Box<dyn BufRead> = if opt.stdin {
-        Box::new(stdin.lock())
+
+    aoc2020::repl::run(day, part, input, stdin.lock(), io::stdout())
+}
+
+fn k_sum(source: Source, k: usize, goal: i64, distinct: bool, format: Format) -> Result<()> {
+    if k > day01::MAX_K {
+        let message = format!("k can be at most {}", day01::MAX_K);
+        return Err(io::Error::new(ErrorKind::InvalidInput, message).into());
+    }
+
+    let (input, _) = source.open(1)?;
+    let expenses = day01::parse(input)?;
+
+    // Values of each sum, with either its indices or the number of ways to
+    // pick them
+    let sums: Vec<(Vec<i32>, Option<Vec<usize>>, u64)> = if distinct {
+        day01::distinct_k_sums(&expenses, k, goal)
+            .into_iter()
+            .map(|(values, ways)| (values, None, ways))
+            .collect()
+    } else {
+        day01::k_sum(&expenses, k, goal)
+            .into_iter()
+            .map(|indices| {
+                let values = indices.iter().map(|&i| expenses[i]).collect();
+                (values, Some(indices), 1)
+            })
+            .collect()
+    };
+
+    let join = |items: &[String], separator| items.join(separator);
+
+    let lines = sums.iter().map(|(values, indices, ways)| {
+        let product = values
+            .iter()
+            .try_fold(1_i128, |product, &v| product.checked_mul(v as i128));
+
+        let indices: Option<Vec<String>> = indices
+            .as_ref()
+            .map(|i| i.iter().map(|i| i.to_string()).collect());
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+
+        match format {
+            Format::Text => format!(
+                "{}{} = {}, product {}{}",
+                indices.map_or(String::new(), |i| format!("{}: ", join(&i, ", "))),
+                join(&values, " + "),
+                goal,
+                product.map_or("too big".to_string(), |p| p.to_string()),
+                if distinct {
+                    format!(" ({} way{})", ways, if *ways == 1 { "" } else { "s" })
+                } else {
+                    String::new()
+                }
+            ),
+            Format::Json => format!(
+                r#"{{{}"expenses":[{}],{}"product":{}}}"#,
+                indices.map_or(String::new(), |i| format!(
+                    r#""indices":[{}],"#,
+                    join(&i, ",")
+                )),
+                join(&values, ","),
+                if distinct {
+                    format!(r#""ways":{},"#, ways)
+                } else {
+                    String::new()
+                },
+                product.map_or("null".to_string(), |p| json_string(&p.to_string()))
+            ),
+        }
+    });
+
+    match format {
+        Format::Text => {
+            for line in lines {
+                println!("{}", line);
+            }
+
+            let plural = if sums.len() == 1 { "" } else { "s" };
+            println!("{} solution{}", sums.len(), plural);
+        }
+        Format::Json => print_json_array(lines),
+    }
+
+    Ok(())
+}
+
+fn check_policy(
+    source: Source,
+    policy: &day02::Policy,
+    unit: day02::Unit,
+    format: Format,
+) -> Result<()> {
+    let (input, _) = source.open(2)?;
+    let passwords = day02::parse(input)?;
+    let report = day02::report(policy, unit, &passwords);
+
+    match format {
+        Format::Text => {
+            for (line, ((_, password), failures)) in passwords.iter().zip(&report).enumerate() {
+                if !failures.is_empty() {
+                    let failures: Vec<String> = failures.iter().map(|f| f.to_string()).collect();
+                    println!("{}: {}: {}", line + 1, password, failures.join(", "));
+                }
+            }
+
+            let valid = report.iter().filter(|failures| failures.is_empty()).count();
+            println!("{} of {} passwords valid", valid, passwords.len());
+        }
+        Format::Json => print_json_array(passwords.iter().zip(&report).enumerate().map(
+            |(line, ((_, password), failures))| {
+                let failures: Vec<String> = failures
+                    .iter()
+                    .map(|f| json_string(&f.to_string()))
+                    .collect();
+
+                format!(
+                    r#"{{"line":{},"password":{},"failures":[{}]}}"#,
+                    line + 1,
+                    json_string(password),
+                    failures.join(",")
+                )
+            },
+        )),
+    }
+
+    Ok(())
+}
+
+fn path(source: Source, dx: isize, dy: usize, draw: bool, format: Format) -> Result<()> {
+    if dy == 0 {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "The slope has to go down").into());
+    }
+
+    let (input, _) = source.open(3)?;
+    let forest = day03::parse(input)?;
+    let path = day03::path(&forest, (dx, dy));
+    let trees = path.iter().filter(|&&pos| forest[pos]).count();
+
+    match format {
+        Format::Text => {
+            if draw {
+                print!("{}", day03::trace(&forest, &path));
+            }
+            println!("{} trees on {} squares", trees, path.len());
+        }
+        Format::Json => {
+            let squares: Vec<String> = path.iter().map(|(x, y)| format!("[{},{}]", x, y)).collect();
+            let trace = if draw {
+                format!(
+                    r#","trace":{}"#,
+                    json_string(&day03::trace(&forest, &path).to_string())
+                )
+            } else {
+                String::new()
+            };
+
+            println!(
+                r#"{{"slope":[{},{}],"trees":{},"path":[{}]{}}}"#,
+                dx,
+                dy,
+                trees,
+                squares.join(","),
+                trace
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn slopes(source: Source, max_dx: isize, max_dy: usize, most: bool, format: Format) -> Result<()> {
+    let (input, _) = source.open(3)?;
+    let forest = day03::parse(input)?;
+
+    let (dxs, dys) = (-max_dx..=max_dx, 1..=max_dy);
+    let best = if most {
+        day03::most_trees(&forest, dxs, dys)
     } else {
-        Box::new(aoc2020::input_file(opt.day)?)
+        day03::fewest_trees(&forest, dxs, dys)
     };
+    let ((dx, dy), trees) =
+        best.ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "No slope within the bounds"))?;
 
-    match opt.day {
-        1 => day01::solve(input, opt.part)?,
-        2 => day02::solve(input, opt.part)?,
-        3 => day03::solve(input, opt.part)?,
-        4 => day04::solve(input, opt.part)?,
-        5 => day05::solve(input, opt.part)?,
-        6 => day06::solve(input, opt.part)?,
-        7 => day07::solve(input, opt.part)?,
-        8 => day08::solve(input, opt.part)?,
-        9 => day09::solve(input, opt.part)?,
-        10 => day10::solve(input, opt.part)?,
-        11 => day11::solve(input, opt.part)?,
-        12 => day12::solve(input, opt.part)?,
-        13 => day13::solve(input, opt.part)?,
-        14 => day14::solve(input, opt.part)?,
-        15 => day15::solve(input, opt.part)?,
-        16 => day16::solve(input, opt.part)?,
-        17 => day17::solve(input, opt.part)?,
-        18 => day18::solve(input, opt.part)?,
-        19 => day19::solve(input, opt.part)?,
-        20 => day20::solve(input, opt.part)?,
-        21 => day21::solve(input, opt.part)?,
-        22 => day22::solve(input, opt.part)?,
-        23 => day23::solve(input, opt.part)?,
-        24 => day24::solve(input, opt.part)?,
-        25 => day25::solve(input, opt.part)?,
-        _ => unimplemented!(),
+    match format {
+        Format::Text => println!("{}, {}: {} trees", dx, dy, trees),
+        Format::Json => println!(r#"{{"slope":[{},{}],"trees":{}}}"#, dx, dy, trees),
     }
 
     Ok(())
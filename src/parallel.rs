@@ -0,0 +1,105 @@
+//! Loops over independent items, run on all the cores with the `parallel`
+//! feature (with [rayon](https://docs.rs/rayon)) and in order without it.
+//!
+//! Both versions take the same closures, so code using them builds either way.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// `f` applied to each item, with the results in the order of the items
+#[cfg(feature = "parallel")]
+pub fn map<I, U>(items: I, f: impl Fn(I::Item) -> U + Sync + Send) -> Vec<U>
+where
+    I: IntoParallelIterator,
+    U: Send,
+{
+    items.into_par_iter().map(f).collect()
+}
+
+/// `f` applied to each item, with the results in the order of the items
+#[cfg(not(feature = "parallel"))]
+pub fn map<I, U>(items: I, f: impl Fn(I::Item) -> U + Sync + Send) -> Vec<U>
+where
+    I: IntoIterator,
+    U: Send,
+{
+    items.into_iter().map(f).collect()
+}
+
+/// First result of `f` which is `Some`, in the order of the items (later items
+/// may still be tried in parallel)
+#[cfg(feature = "parallel")]
+pub fn find_map_first<I, U>(items: I, f: impl Fn(I::Item) -> Option<U> + Sync + Send) -> Option<U>
+where
+    I: IntoParallelIterator,
+    U: Send,
+{
+    items.into_par_iter().find_map_first(f)
+}
+
+/// First result of `f` which is `Some`, in the order of the items (later items
+/// may still be tried in parallel)
+#[cfg(not(feature = "parallel"))]
+pub fn find_map_first<I, U>(items: I, f: impl Fn(I::Item) -> Option<U> + Sync + Send) -> Option<U>
+where
+    I: IntoIterator,
+    U: Send,
+{
+    items.into_iter().find_map(f)
+}
+
+/// Items folded into accumulators started with `init`: a single one in order,
+/// or one per batch of items in parallel, which are then combined with `merge`
+#[cfg(feature = "parallel")]
+pub fn fold<I, A>(
+    items: I,
+    init: impl Fn() -> A + Sync + Send,
+    fold: impl Fn(A, I::Item) -> A + Sync + Send,
+    merge: impl Fn(A, A) -> A + Sync + Send,
+) -> A
+where
+    I: IntoParallelIterator,
+    A: Send,
+{
+    items.into_par_iter().fold(&init, fold).reduce(&init, merge)
+}
+
+/// Items folded into accumulators started with `init`: a single one in order,
+/// or one per batch of items in parallel, which are then combined with `merge`
+#[cfg(not(feature = "parallel"))]
+pub fn fold<I, A>(
+    items: I,
+    init: impl Fn() -> A + Sync + Send,
+    fold: impl Fn(A, I::Item) -> A + Sync + Send,
+    _merge: impl Fn(A, A) -> A + Sync + Send,
+) -> A
+where
+    I: IntoIterator,
+    A: Send,
+{
+    items.into_iter().fold(init(), fold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loops() {
+        let items: Vec<usize> = (0..1000).collect();
+
+        assert_eq!(
+            map(&items, |n| n * 2),
+            (0..2000).step_by(2).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            find_map_first(&items, |&n| if n % 7 == 6 { Some(n) } else { None }),
+            Some(6)
+        );
+        assert_eq!(find_map_first(&items, |_| None::<usize>), None);
+        assert_eq!(
+            fold(&items, || 0, |sum, n| sum + n, |a, b| a + b),
+            items.iter().sum()
+        );
+    }
+}
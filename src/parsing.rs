@@ -0,0 +1,282 @@
+//! Streaming building blocks for the parsers of the puzzle inputs.
+//!
+//! Lines come from `BufRead::lines`, so both `\n` and `\r\n` line endings are
+//! accepted, with or without a newline at the end of the input, and blank
+//! lines at the end of the input are ignored.
+
+use std::collections::VecDeque;
+use std::io::prelude::*;
+use std::io::Lines;
+
+use crate::error::{column, Context};
+use crate::{Error, Result};
+
+/// Lines of the input with their number, see `lines`
+pub struct NumberedLines<B> {
+    lines: Lines<B>,
+    line_number: usize,
+    /// Blank lines, held back until a line after them shows they aren't at
+    /// the end of the input
+    blanks: VecDeque<(usize, String)>,
+    after_blanks: Option<(usize, String)>,
+}
+
+impl<B: BufRead> Iterator for NumberedLines<B> {
+    type Item = Result<(usize, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.after_blanks.is_some() {
+            return self
+                .blanks
+                .pop_front()
+                .or_else(|| self.after_blanks.take())
+                .map(Ok);
+        }
+
+        for line in &mut self.lines {
+            self.line_number += 1;
+
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+
+            if line.trim().is_empty() {
+                self.blanks.push_back((self.line_number, line));
+            } else {
+                self.after_blanks = Some((self.line_number, line));
+                return self.next();
+            }
+        }
+
+        None
+    }
+}
+
+/// Lines of the input with their number (starting at 1), without the blank
+/// lines at the end
+pub fn lines<B: BufRead>(input: B) -> NumberedLines<B> {
+    NumberedLines {
+        lines: input.lines(),
+        line_number: 0,
+        blanks: VecDeque::new(),
+        after_blanks: None,
+    }
+}
+
+/// One record per line, with the errors located on their line
+pub fn records<T>(
+    input: impl BufRead,
+    mut parse: impl FnMut(&str) -> Result<T>,
+) -> impl Iterator<Item = Result<T>> {
+    lines(input).map(move |line| {
+        let (n, line) = line?;
+        parse(&line).on_line(n)
+    })
+}
+
+/// The only line of the input, ignoring blank lines after it
+pub fn single_line(input: impl BufRead) -> Result<String> {
+    let mut lines = lines(input);
+
+    let (_, line) = lines
+        .next()
+        .ok_or_else(|| Error::parse("Empty input").on_line(1))??;
+
+    for next in lines {
+        let (n, next) = next?;
+
+        if !next.trim().is_empty() {
+            return Err(Error::parse("Expected a single line").on_line(n));
+        }
+    }
+
+    Ok(line)
+}
+
+/// Consecutive non blank lines
+#[derive(Debug, Clone, Default)]
+pub struct Block {
+    /// Number of the first line of the block
+    pub first_line: usize,
+    pub lines: Vec<String>,
+}
+
+impl Block {
+    /// Lines of the block with their number
+    pub fn lines(&self) -> impl Iterator<Item = (usize, &str)> {
+        (self.first_line..).zip(self.lines.iter().map(String::as_str))
+    }
+
+    /// One record per line of the block, with the errors located on their line
+    pub fn records<'a, T: 'a>(
+        &'a self,
+        mut parse: impl FnMut(&str) -> Result<T> + 'a,
+    ) -> impl Iterator<Item = Result<T>> + 'a {
+        self.lines().map(move |(n, line)| parse(line).on_line(n))
+    }
+
+    /// The first line of the block, which has to be `header`
+    pub fn expect_header(&self, header: &str) -> Result<()> {
+        match self.lines.first() {
+            Some(line) if line == header => Ok(()),
+            _ => Err(Error::parse(format!("Expected {:?}", header)).on_line(self.first_line)),
+        }
+    }
+
+    /// Lines of the block after the header
+    pub fn body(&self) -> Block {
+        Block {
+            first_line: self.first_line + 1,
+            lines: self.lines.iter().skip(1).cloned().collect(),
+        }
+    }
+}
+
+/// Blocks of lines separated by blank lines, see `blocks`
+pub struct Blocks<B> {
+    lines: Lines<B>,
+    line_number: usize,
+}
+
+impl<B: BufRead> Iterator for Blocks<B> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = Block::default();
+
+        for line in &mut self.lines {
+            self.line_number += 1;
+
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+
+            if line.trim().is_empty() {
+                if block.lines.is_empty() {
+                    continue;
+                }
+
+                return Some(Ok(block));
+            }
+
+            if block.lines.is_empty() {
+                block.first_line = self.line_number;
+            }
+
+            block.lines.push(line);
+        }
+
+        if block.lines.is_empty() {
+            None
+        } else {
+            Some(Ok(block))
+        }
+    }
+}
+
+/// Blocks of lines separated by (any number of) blank lines
+pub fn blocks<B: BufRead>(input: B) -> Blocks<B> {
+    Blocks {
+        lines: input.lines(),
+        line_number: 0,
+    }
+}
+
+/// Items of a comma separated list, with the errors located at their column
+pub fn comma_list<T>(line: &str, mut parse: impl FnMut(&str) -> Result<T>) -> Result<Vec<T>> {
+    line.split(',')
+        .map(|item| {
+            let item = item.trim();
+            parse(item).at_column(column(line, item))
+        })
+        .collect()
+}
+
+/// Key and value on each side of the first `separator`
+pub fn key_value<'a>(pair: &'a str, separator: &str) -> Result<(&'a str, &'a str)> {
+    pair.split_once(separator)
+        .ok_or_else(|| Error::parse(format!("Expected \"<key>{}<value>\"", separator)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crlf_and_missing_trailing_newline() {
+        let numbers: Vec<u32> = records("1\r\n2\r\n3".as_bytes(), |l| Ok(l.parse()?))
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(numbers, [1, 2, 3]);
+    }
+
+    #[test]
+    fn trailing_blank_lines() {
+        let lines: Vec<(usize, String)> = lines("1\n\n \n2\n\n\r\n".as_bytes())
+            .collect::<Result<_>>()
+            .unwrap();
+        let numbers: Vec<usize> = lines.iter().map(|(n, _)| *n).collect();
+
+        assert_eq!(numbers, [1, 2, 3, 4]);
+        assert_eq!(lines[2].1, " ");
+
+        let numbers: Vec<u32> = records("1721\n299\n\n".as_bytes(), |l| Ok(l.parse()?))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(numbers, [1721, 299]);
+    }
+
+    #[test]
+    fn record_errors() {
+        let error = records("1\n2\nx".as_bytes(), |l| Ok(l.parse::<u32>()?))
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "line 3: invalid digit found in string");
+    }
+
+    #[test]
+    fn blank_line_separated_blocks() {
+        let blocks: Vec<Block> = blocks("\na\nb\r\n\r\n  \nc\n\n".as_bytes())
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].lines().collect::<Vec<_>>(), [(2, "a"), (3, "b")]);
+        assert_eq!(blocks[1].lines().collect::<Vec<_>>(), [(6, "c")]);
+
+        assert!(blocks[0].expect_header("a").is_ok());
+        assert_eq!(
+            blocks[1].expect_header("a").unwrap_err().to_string(),
+            "line 6: Expected \"a\""
+        );
+        assert_eq!(blocks[0].body().lines().collect::<Vec<_>>(), [(3, "b")]);
+    }
+
+    #[test]
+    fn single_lines() {
+        assert_eq!(single_line("0,3,6\r\n\n".as_bytes()).unwrap(), "0,3,6");
+        assert!(single_line("".as_bytes()).is_err());
+
+        let error = single_line("1\n2".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: Expected a single line");
+    }
+
+    #[test]
+    fn lists_and_pairs() {
+        let list = comma_list("1, 2,x", |s| Ok(s.parse::<u32>()?));
+        assert_eq!(
+            list.unwrap_err().to_string(),
+            "column 6: invalid digit found in string"
+        );
+
+        assert_eq!(key_value("hgt:183cm", ":").unwrap(), ("hgt", "183cm"));
+        assert_eq!(
+            key_value("hgt", ":").unwrap_err().to_string(),
+            "Expected \"<key>:<value>\""
+        );
+    }
+}
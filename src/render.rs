@@ -0,0 +1,338 @@
+//! Frames of the cellular automata (days 11, 17 and 24), to watch them in a
+//! terminal or export them as images when debugging their rules.
+//!
+//! The simulations call back with each generation (`stable_count_with` and
+//! `run_steps_with`), [`simulation`] collects them into frames of the same
+//! size, which are then drawn with ANSI escape codes or written as PPM images
+//! or an animated GIF.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::io::{self, ErrorKind};
+
+use crate::grid::Grid;
+use crate::{day11, day17, day24, Result, Solution};
+
+/// Colour of a pixel, as red, green and blue
+pub type Rgb = [u8; 3];
+
+/// Image of a generation, with one pixel per cell
+pub type Frame = Grid<Rgb>;
+
+const BACKGROUND: Rgb = [24, 24, 32];
+const FOREGROUND: Rgb = [240, 200, 60];
+
+/// Cell drawn as a single pixel
+pub trait Colour {
+    fn colour(&self) -> Rgb;
+}
+
+/// Set cells (active cubes, black tiles) stand out of the background
+impl Colour for bool {
+    fn colour(&self) -> Rgb {
+        if *self {
+            FOREGROUND
+        } else {
+            BACKGROUND
+        }
+    }
+}
+
+impl Colour for day11::Cell {
+    fn colour(&self) -> Rgb {
+        match self {
+            day11::Cell::SeatOccupied => [220, 60, 50],
+            day11::Cell::SeatEmpty => [80, 180, 90],
+            day11::Cell::Floor => BACKGROUND,
+        }
+    }
+}
+
+pub fn frame<T: Colour>(grid: &Grid<T>) -> Frame {
+    grid.map(|_, cell| cell.colour())
+}
+
+/// Rectangle around the cells of an unbounded simulation, corners included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: (isize, isize),
+    pub max: (isize, isize),
+}
+
+impl Bounds {
+    /// Smallest bounds containing all the points, `None` without any
+    pub fn of(points: impl IntoIterator<Item = (isize, isize)>) -> Option<Self> {
+        points
+            .into_iter()
+            .map(|p| Bounds { min: p, max: p })
+            .reduce(Bounds::union)
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Bounds {
+            min: (self.min.0.min(other.min.0), self.min.1.min(other.min.1)),
+            max: (self.max.0.max(other.max.0), self.max.1.max(other.max.1)),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.0 - self.min.0) as usize + 1
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.1 - self.min.1) as usize + 1
+    }
+
+    /// Grid covering the bounds, where the points are set (the ones out of
+    /// bounds are ignored)
+    pub fn plot(&self, points: impl IntoIterator<Item = (isize, isize)>) -> Grid<bool> {
+        let mut grid = Grid::filled(self.width(), self.height(), false);
+
+        for (x, y) in points {
+            let pos = ((x - self.min.0) as usize, (y - self.min.1) as usize);
+
+            if let Some(cell) = grid.get_mut(pos) {
+                *cell = true;
+            }
+        }
+
+        grid
+    }
+}
+
+/// Frames of each generation of a part of a simulation, from the initial one
+/// to the last one the part looks at. Only days 11, 17 and 24 have one.
+pub fn simulation(day: u8, part: u8, input: impl BufRead) -> Result<Vec<Frame>> {
+    match (day, part) {
+        (11, 1..=2) => {
+            let mut map = day11::Day11::parse(input)?;
+            let mut frames = Vec::new();
+
+            let (tolerance, at_distance) = if part == 1 { (4, false) } else { (5, true) };
+            map.stable_count_with(tolerance, at_distance, |map| {
+                frames.push(frame(map.cells()))
+            });
+
+            Ok(frames)
+        }
+        (17, 1..=2) => {
+            let grid = day17::Day17::parse(input)?;
+            let mut planes = Vec::new();
+
+            // Only the slice through the origin of the extra dimensions is drawn
+            if part == 1 {
+                grid.extend::<3>()
+                    .run_steps_with(6, |grid| planes.push(grid.plane().collect()));
+            } else {
+                grid.extend::<4>()
+                    .run_steps_with(6, |grid| planes.push(grid.plane().collect()));
+            }
+
+            Ok(plot_all(&planes))
+        }
+        (24, 1..=2) => {
+            let mut grid = day24::Grid::from_dirs(day24::Day24::parse(input)?);
+            let mut floors = Vec::new();
+
+            let steps = if part == 1 { 0 } else { 100 };
+            grid.run_steps_with(steps, |grid| floors.push(grid.pixels().collect()));
+
+            Ok(plot_all(&floors))
+        }
+        (11, _) | (17, _) | (24, _) => {
+            Err(io::Error::new(ErrorKind::InvalidInput, "No simulation for this part").into())
+        }
+        _ => Err(io::Error::new(ErrorKind::InvalidInput, "No simulation for this day").into()),
+    }
+}
+
+/// Frames of sets of points, all plotted within the same bounds
+fn plot_all(generations: &[Vec<(isize, isize)>]) -> Vec<Frame> {
+    let bounds = Bounds::of(generations.iter().flatten().copied()).unwrap_or(Bounds {
+        min: (0, 0),
+        max: (0, 0),
+    });
+
+    generations
+        .iter()
+        .map(|points| frame(&bounds.plot(points.iter().copied())))
+        .collect()
+}
+
+/// Text drawing the frame in a terminal with 24-bit colours, two rows of
+/// pixels per line (with upper half blocks) so that they are about square
+pub fn ansi(frame: &Frame) -> String {
+    let mut text = String::new();
+    let rows: Vec<&[Rgb]> = frame.rows().collect();
+
+    for pair in rows.chunks(2) {
+        for (x, top) in pair[0].iter().enumerate() {
+            let bottom = pair.get(1).map_or(BACKGROUND, |row| row[x]);
+
+            text.push_str(&format!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+            ));
+        }
+
+        text.push_str("\x1b[0m\n");
+    }
+
+    text
+}
+
+/// Binary PPM image of the frame, with each pixel drawn as a square of
+/// `scale` by `scale` pixels
+pub fn write_ppm(frame: &Frame, scale: usize, mut out: impl Write) -> io::Result<()> {
+    write!(
+        out,
+        "P6\n{} {}\n255\n",
+        frame.width() * scale,
+        frame.height() * scale
+    )?;
+
+    for row in frame.rows() {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|pixel| pixel.iter().cycle().take(3 * scale))
+            .copied()
+            .collect();
+
+        for _ in 0..scale {
+            out.write_all(&line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Animated GIF of the frames, looping forever, with `delay` hundredths of a
+/// second between them. The frames must all have the size of the first one,
+/// and use at most 256 colours in total.
+pub fn write_gif(frames: &[Frame], scale: usize, delay: u16, out: impl Write) -> Result<()> {
+    let invalid = |message: &str| io::Error::new(ErrorKind::InvalidInput, message.to_string());
+    let gif_error = |e: gif::EncodingError| io::Error::other(e);
+
+    let (width, height) = frames
+        .first()
+        .map_or((0, 0), |f| (f.width() * scale, f.height() * scale));
+    if frames
+        .iter()
+        .any(|f| f.width() * scale != width || f.height() * scale != height)
+    {
+        return Err(invalid("Frames of different sizes").into());
+    }
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(invalid("Frames too big for a GIF").into());
+    }
+
+    let mut palette = HashMap::<Rgb, u8>::new();
+    for &pixel in frames.iter().flat_map(|f| f.cells()) {
+        let next = palette.len();
+        if next == 256 && !palette.contains_key(&pixel) {
+            return Err(invalid("More than 256 colours").into());
+        }
+        palette.entry(pixel).or_insert(next as u8);
+    }
+
+    let mut colours = vec![0; 3 * palette.len()];
+    for (pixel, &index) in &palette {
+        colours[3 * index as usize..3 * index as usize + 3].copy_from_slice(pixel);
+    }
+
+    let mut encoder =
+        gif::Encoder::new(out, width as u16, height as u16, &colours).map_err(gif_error)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(gif_error)?;
+
+    for frame in frames {
+        let mut buffer = Vec::with_capacity(width * height);
+        for row in frame.rows() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|pixel| std::iter::repeat_n(palette[pixel], scale))
+                .collect();
+
+            for _ in 0..scale {
+                buffer.extend_from_slice(&line);
+            }
+        }
+
+        let frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            delay,
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        };
+        encoder.write_frame(&frame).map_err(gif_error)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds() {
+        let bounds = Bounds::of(vec![(1, -2), (-1, 0), (0, 1)]).unwrap();
+
+        assert_eq!((bounds.min, bounds.max), ((-1, -2), (1, 1)));
+        assert_eq!((bounds.width(), bounds.height()), (3, 4));
+        assert_eq!(
+            bounds.plot(vec![(1, -2), (0, 0), (5, 5)]).to_string(),
+            "..#\n...\n.#.\n...\n"
+        );
+        assert_eq!(Bounds::of(vec![]), None);
+    }
+
+    #[test]
+    fn images() {
+        let frame = frame(&Grid::new(2, vec![true, false, false, true]));
+
+        let mut ppm = Vec::new();
+        write_ppm(&frame, 2, &mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+        assert_eq!(ppm[11..14], FOREGROUND);
+        assert_eq!(ppm[17..20], BACKGROUND);
+
+        let mut gif = Vec::new();
+        write_gif(&[frame.clone(), frame.clone()], 3, 10, &mut gif).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(&gif[..]).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (6, 6));
+        let mut count = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 2);
+
+        let other = super::frame(&Grid::new(1, vec![true]));
+        assert!(write_gif(&[frame.clone(), other], 1, 10, &mut Vec::new()).is_err());
+
+        let text = ansi(&frame);
+        assert_eq!(text.lines().count(), 1);
+        assert_eq!(text.matches('\u{2580}').count(), 2);
+    }
+
+    #[test]
+    fn simulations() {
+        let frames = simulation(17, 1, ".#.\n..#\n###\n".as_bytes()).unwrap();
+
+        assert_eq!(frames.len(), 7);
+        assert!(frames
+            .iter()
+            .all(|f| (f.width(), f.height()) == (frames[0].width(), frames[0].height())));
+
+        let frames = simulation(11, 1, "L.L\nLLL\n".as_bytes()).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1][(0, 0)], [220, 60, 50]);
+
+        assert!(simulation(1, 1, "".as_bytes()).is_err());
+        assert!(simulation(24, 3, "".as_bytes()).is_err());
+    }
+}
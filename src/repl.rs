@@ -0,0 +1,388 @@
+//! Interactive exploration of the puzzles which run step by step: the
+//! handheld console (day 8), the seating system (day 11), the games of Combat
+//! (day 22) and the cups (day 23), as well as expressions of day 18.
+
+use std::collections::VecDeque;
+use std::io::prelude::*;
+use std::io::{self, ErrorKind};
+
+use crate::day08::{self, CPU};
+use crate::day11::{self, Map};
+use crate::day18::{self, Expr};
+use crate::day22::{self, Game};
+use crate::day23::{self, Circle, CUP_COUNT};
+use crate::{Error, Result};
+
+const HELP: &str = "\
+step [n]  run n steps (1 by default)
+run       run until there are no steps left
+show      print the current state
+reset     go back to the initial state
+eval <e>  evaluate an expression (day 18)
+help      print this help
+quit      leave (as does the end of the commands)";
+
+/// State of a puzzle, explored from the REPL
+trait Session: Clone {
+    /// Run one step, returns false instead when there are none left
+    fn step(&mut self) -> bool;
+
+    /// Current state, as text
+    fn show(&self) -> String;
+
+    fn eval(&self, _expr: &str) -> Result<String> {
+        Err(Error::parse("No expressions on this day"))
+    }
+}
+
+impl Session for CPU {
+    fn step(&mut self) -> bool {
+        CPU::step(self)
+    }
+
+    fn show(&self) -> String {
+        let next = match self.next_instr() {
+            Some((instr, false)) => instr.to_string(),
+            Some((instr, true)) => format!("{} (already run, the program loops)", instr),
+            None if self.terminated() => "none, the program terminated".to_string(),
+            None => "none, the program counter is out of the program".to_string(),
+        };
+
+        format!(
+            "program counter: {}\naccumulator: {}\nnext instruction: {}",
+            self.program_counter(),
+            self.accumulator(),
+            next
+        )
+    }
+}
+
+/// Seats of day 11, with the rules of a part
+#[derive(Clone)]
+struct Seats {
+    map: Map,
+    tolerance: usize,
+    at_distance: bool,
+    generation: usize,
+    stable: bool,
+}
+
+impl Session for Seats {
+    fn step(&mut self) -> bool {
+        let (map, change) = self.map.step(self.tolerance, self.at_distance);
+
+        if change {
+            self.map = map;
+            self.generation += 1;
+        } else {
+            self.stable = true;
+        }
+
+        change
+    }
+
+    fn show(&self) -> String {
+        let occupied = self
+            .map
+            .cells()
+            .cells()
+            .iter()
+            .filter(|&&cell| cell == day11::Cell::SeatOccupied)
+            .count();
+
+        format!(
+            "{}generation {}: {} occupied seats{}",
+            self.map.cells(),
+            self.generation,
+            occupied,
+            if self.stable { " (stable)" } else { "" }
+        )
+    }
+}
+
+/// Homework of day 18
+#[derive(Clone)]
+struct Homework(Vec<Expr>);
+
+impl Session for Homework {
+    fn step(&mut self) -> bool {
+        false
+    }
+
+    fn show(&self) -> String {
+        let sum = |sum: Option<usize>| sum.map_or("too big".to_string(), |s| s.to_string());
+
+        format!(
+            "{} expressions, which sum to {} from left to right, or {} with additions first",
+            self.0.len(),
+            sum(day18::part_1(self.0.clone())),
+            sum(day18::part_2(self.0.clone()))
+        )
+    }
+
+    fn eval(&self, expr: &str) -> Result<String> {
+        let expr: Expr = expr.parse()?;
+        let overflow = || io::Error::new(ErrorKind::InvalidInput, "The value is too big");
+
+        Ok(format!(
+            "{} from left to right, {} with additions first",
+            expr.clone().eval().ok_or_else(overflow)?,
+            expr.eval_add_precedence().ok_or_else(overflow)?
+        ))
+    }
+}
+
+impl Session for Game {
+    fn step(&mut self) -> bool {
+        self.round()
+    }
+
+    fn show(&self) -> String {
+        let deck = |cards: &VecDeque<usize>| {
+            let cards: Vec<String> = cards.iter().map(|c| c.to_string()).collect();
+            cards.join(", ")
+        };
+        let (player_1, player_2) = self.decks();
+
+        let mut text = format!(
+            "Player 1's deck: {}\nPlayer 2's deck: {}",
+            deck(player_1),
+            deck(player_2)
+        );
+
+        if let Some(winner) = self.winner() {
+            let score = day22::winner_score(self.clone().into_decks());
+            text.push_str(&format!(
+                "\nPlayer {} wins with a score of {}",
+                winner, score
+            ));
+        }
+
+        text
+    }
+}
+
+/// Cups of day 23, for the moves of part 1
+#[derive(Clone)]
+struct Cups {
+    circle: Circle<CUP_COUNT>,
+    moves: usize,
+}
+
+impl Session for Cups {
+    fn step(&mut self) -> bool {
+        if self.moves == day23::MOVES {
+            return false;
+        }
+
+        self.circle.step();
+        self.moves += 1;
+
+        true
+    }
+
+    fn show(&self) -> String {
+        let cups: Vec<usize> = self.circle.cups().collect();
+        let one = cups.iter().position(|&cup| cup == 1).unwrap_or(0);
+        let after_one: String = cups[one + 1..]
+            .iter()
+            .chain(&cups[..one])
+            .map(|cup| cup.to_string())
+            .collect();
+
+        let circle: Vec<String> = cups
+            .iter()
+            .enumerate()
+            .map(|(i, cup)| {
+                if i == 0 {
+                    format!("({})", cup)
+                } else {
+                    cup.to_string()
+                }
+            })
+            .collect();
+
+        format!(
+            "move {}\ncups: {}\nlabels after cup 1: {}",
+            self.moves,
+            circle.join(" "),
+            after_one
+        )
+    }
+}
+
+/// Explore the puzzle input of a day with commands read from `commands`,
+/// replying to `out`. The part picks the rules of days 11 and 22.
+pub fn run(
+    day: u8,
+    part: u8,
+    input: impl BufRead,
+    commands: impl BufRead,
+    out: impl Write,
+) -> Result<()> {
+    if !(1..=2).contains(&part) {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "No solution for this part").into());
+    }
+
+    match day {
+        8 => repl(CPU::new(&day08::parse(input)?), commands, out),
+        11 => {
+            let (tolerance, at_distance) = if part == 1 { (4, false) } else { (5, true) };
+            let seats = Seats {
+                map: Map::parse(input)?,
+                tolerance,
+                at_distance,
+                generation: 0,
+                stable: false,
+            };
+
+            repl(seats, commands, out)
+        }
+        18 => repl(Homework(day18::parse(input)?), commands, out),
+        22 => repl(Game::new(day22::parse(input)?, part == 2), commands, out),
+        23 => {
+            let cups = Cups {
+                circle: Circle::new(&day23::parse(input)?),
+                moves: 0,
+            };
+
+            repl(cups, commands, out)
+        }
+        _ => Err(io::Error::new(ErrorKind::InvalidInput, "No REPL for this day").into()),
+    }
+}
+
+fn repl<S: Session>(initial: S, commands: impl BufRead, mut out: impl Write) -> Result<()> {
+    let mut state = initial.clone();
+    let mut steps = 0;
+
+    writeln!(out, "{}\n(type help for the commands)", state.show())?;
+
+    for line in commands.lines() {
+        let line = line?;
+        let (command, arg) = match line.trim().split_once(' ') {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line.trim(), ""),
+        };
+
+        let reply = match command {
+            "" => Ok(String::new()),
+            "step" | "s" => {
+                let count = if arg.is_empty() {
+                    Ok(1)
+                } else {
+                    arg.parse().map_err(Error::from)
+                };
+
+                count.map(|count| {
+                    let done = (0..count).take_while(|_| state.step()).count();
+                    steps += done;
+
+                    let end = if done < count { ", no steps left" } else { "" };
+                    format!("{}\n(step {}{})", state.show(), steps, end)
+                })
+            }
+            "run" => {
+                while state.step() {
+                    steps += 1;
+                }
+
+                Ok(format!("{}\n(step {}, no steps left)", state.show(), steps))
+            }
+            "show" | "p" => Ok(state.show()),
+            "reset" => {
+                state = initial.clone();
+                steps = 0;
+                Ok(state.show())
+            }
+            "eval" => state.eval(arg),
+            "help" => Ok(HELP.to_string()),
+            "quit" | "q" => break,
+            _ => Err(Error::parse(format!(
+                "Unknown command {:?}, try help",
+                command
+            ))),
+        };
+
+        match reply {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => writeln!(out, "{}", text)?,
+            Err(e) => writeln!(out, "error: {}", e)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(day: u8, part: u8, input: &str, commands: &str) -> String {
+        let mut out = Vec::new();
+        run(day, part, input.as_bytes(), commands.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn console() {
+        let program = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6\n";
+        let out = session(8, 1, program, "step 2\nrun\n");
+
+        assert!(out.contains("accumulator: 1\nnext instruction: jmp +4\n(step 2)"));
+        assert!(out.contains("accumulator: 5\nnext instruction: acc +1 (already run"));
+        assert!(out.ends_with("(step 7, no steps left)\n"));
+    }
+
+    #[test]
+    fn games() {
+        let decks = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10\n";
+
+        let out = session(22, 1, decks, "s\nrun\nreset\nstep x\n");
+        assert!(out.contains("Player 1's deck: 2, 6, 3, 1, 9, 5\n"));
+        assert!(out.contains("Player 2 wins with a score of 306\n(step 29, no steps left)"));
+        assert!(out.contains("Player 1's deck: 9, 2, 6, 3, 1\nPlayer 2's deck: 5, 8, 4, 7, 10\n"));
+        assert!(out.ends_with("error: invalid digit found in string\n"));
+
+        let out = session(22, 2, decks, "run\n");
+        assert!(out.contains("Player 2 wins with a score of 291\n(step 17, no steps left)"));
+    }
+
+    #[test]
+    fn cups() {
+        let out = session(23, 1, "389125467\n", "step 10\nstep 1000\nquit\nstep\n");
+
+        assert!(out.contains("move 10\ncups: (8) 3 7 4 1 9 2 6 5\nlabels after cup 1: 92658374\n"));
+        assert!(out.contains("labels after cup 1: 67384529\n(step 100, no steps left)"));
+        assert!(out.ends_with("no steps left)\n"));
+    }
+
+    #[test]
+    fn expressions() {
+        let out = session(
+            18,
+            1,
+            "1 + 2 * 3 + 4\n",
+            "eval 2 * 3 + (4 * 5)\neval 2 +\neval 999999999999 * 999999999999 * 99999999\nfoo\n",
+        );
+
+        assert!(out.starts_with("1 expressions, which sum to 13 from left to right, or 21"));
+        assert!(out.contains("\n26 from left to right, 46 with additions first\n"));
+        assert!(out.contains("\nerror: "));
+        assert!(out.contains("\nerror: The value is too big\n"));
+        assert!(out.ends_with("error: Unknown command \"foo\", try help\n"));
+    }
+
+    #[test]
+    fn seats() {
+        let out = session(11, 1, "L.L\nLLL\n", "run\n");
+
+        assert!(out.contains(
+            "#.#\n#L#\ngeneration 2: 4 occupied seats (stable)\n(step 2, no steps left)"
+        ));
+
+        let mut out = Vec::new();
+        assert!(run(3, 1, "".as_bytes(), "".as_bytes(), &mut out).is_err());
+        assert!(run(11, 3, "".as_bytes(), "".as_bytes(), &mut out).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::any::Any;
+use std::io::prelude::*;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::rng::Rng;
+use crate::{Answer, Cache, Result};
+
+/// Extra part of a puzzle, solved from the input `I`
+pub type BonusPart<I, A> = fn(I) -> Result<A>;
+
+/// Solution to the puzzle of one day, split between parsing the input and
+/// solving each part from the parsed input
+pub trait Solution {
+    /// Day of the puzzle, from 1 to 25
+    const DAY: u8;
+
+    /// Parsed puzzle input, shared by all the parts
+    type Input: Clone + 'static;
+    type Part1: Into<Answer>;
+    type Part2: Into<Answer> + 'static;
+
+    fn parse(input: impl BufRead) -> Result<Self::Input>;
+
+    fn part_1(input: Self::Input) -> Result<Self::Part1>;
+
+    fn part_2(input: Self::Input) -> Result<Self::Part2>;
+
+    /// Extra parts that are not part of the puzzle, numbered from 3 onwards
+    fn bonus_parts() -> &'static [BonusPart<Self::Input, Self::Part2>] {
+        &[]
+    }
+}
+
+/// Generator of random puzzle inputs for a `Solution`, to test and benchmark it
+pub trait Generate: Solution {
+    /// Size of a typical puzzle input, in the unit `generate` uses for this day
+    const INPUT_SIZE: usize;
+
+    /// Random puzzle input of about `size` records, with an answer to both parts
+    fn generate(rng: &mut Rng, size: usize) -> String;
+}
+
+/// Type erased `Solution`, so that all the days can be listed together. Their
+/// inputs can also be generated, and saved by [`Cache`].
+pub trait Day: Sync {
+    fn day(&self) -> u8;
+
+    /// Number of parts, including bonus parts
+    fn parts(&self) -> u8;
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Box<dyn Any>>;
+
+    /// `parse`, through a cache of parsed inputs
+    fn parse_cached(&self, input: &mut dyn BufRead, cache: &Cache) -> Result<Box<dyn Any>>;
+
+    /// Solve a part from the output of `parse`, `None` if there is no such part
+    fn solve(&self, input: &dyn Any, part: u8) -> Option<Result<Answer>>;
+
+    /// Random puzzle input from a seed, of a typical size by default
+    fn generate(&self, seed: u64, size: Option<usize>) -> String;
+}
+
+impl<S> Day for S
+where
+    S: Solution + Generate + Sync,
+    S::Input: Serialize + DeserializeOwned,
+{
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn parts(&self) -> u8 {
+        2 + S::bonus_parts().len() as u8
+    }
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Box<dyn Any>> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn parse_cached(&self, input: &mut dyn BufRead, cache: &Cache) -> Result<Box<dyn Any>> {
+        Ok(Box::new(cache.parse::<S>(input)?))
+    }
+
+    fn solve(&self, input: &dyn Any, part: u8) -> Option<Result<Answer>> {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .expect("Input parsed for another day")
+            .clone();
+
+        let answer = match part {
+            1 => S::part_1(input).map(Into::into),
+            2 => S::part_2(input).map(Into::into),
+            _ => {
+                let bonus = S::bonus_parts().get(part.checked_sub(3)? as usize)?;
+                bonus(input).map(Into::into)
+            }
+        };
+
+        Some(answer)
+    }
+
+    fn generate(&self, seed: u64, size: Option<usize>) -> String {
+        S::generate(&mut Rng::new(seed), size.unwrap_or(S::INPUT_SIZE))
+    }
+}